@@ -0,0 +1,93 @@
+use crate::gilded_rose::{GildedRose, Item};
+use crate::profile::Profile;
+use crate::sales::{Cents, Pricing, SalePolicy};
+use std::fmt::{self, Display};
+
+// When to sell one item. `day` is absolute (`GildedRose::day()` numbering); `sell_in` and
+// `quality` are the item's values on that day.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Advice {
+    pub index: usize,
+    pub name: String,
+    pub day: u32,
+    pub sell_in: i32,
+    pub quality: i32,
+    pub price_cents: u64,
+    // What the item would fetch today, for comparison.
+    pub today_cents: u64,
+}
+
+impl Display for Advice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "day {}, {}, {}, {}, {} (today {})",
+            self.day,
+            self.name,
+            self.sell_in,
+            self.quality,
+            Cents(self.price_cents),
+            Cents(self.today_cents)
+        )
+    }
+}
+
+// Finds the day within `horizon` on which each item fetches the most, by ageing a copy of it
+// with the real rules (`GildedRose::forecast`). The cap at `QUALITY_MAX`, the Backstage drop
+// and registered event dates are therefore accounted for without special cases.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Advisor {
+    pub pricing: Pricing,
+    pub policy: SalePolicy,
+    pub horizon: u32,
+}
+
+impl Advisor {
+    pub fn new(pricing: Pricing, policy: SalePolicy, horizon: u32) -> Advisor {
+        Advisor {
+            pricing,
+            policy,
+            horizon,
+        }
+    }
+
+    // `None` when the item cannot be sold on any day within the horizon (e.g. legendary items
+    // under the default policy). On equal prices the earliest day wins.
+    pub fn advise(&self, rose: &GildedRose, index: usize) -> Option<Advice> {
+        let states = rose.forecast(index, self.horizon)?;
+        let value = |it: &Item| self.value(rose.profile(), it);
+        let today_cents = states.first().and_then(value).unwrap_or(0);
+        let (offset, it, price_cents) = states
+            .iter()
+            .enumerate()
+            .filter_map(|(d, it)| Some((d, it, value(it)?)))
+            // `max_by_key` keeps the last of equal keys; reversing the key on the day keeps the first.
+            .max_by_key(|&(d, _, price)| (price, std::cmp::Reverse(d)))?;
+        Some(Advice {
+            index,
+            name: it.name.clone(),
+            day: rose
+                .day()
+                .saturating_add(u32::try_from(offset).unwrap_or(u32::MAX)),
+            sell_in: it.sell_in,
+            quality: it.quality,
+            price_cents,
+            today_cents,
+        })
+    }
+
+    // Advice for every sellable item, ordered by day and then inventory position.
+    pub fn schedule(&self, rose: &GildedRose) -> Vec<Advice> {
+        let mut schedule: Vec<Advice> = (0..rose.items.len())
+            .filter_map(|index| self.advise(rose, index))
+            .collect();
+        schedule.sort_by_key(|a| (a.day, a.index));
+        schedule
+    }
+
+    fn value(&self, profile: &Profile, it: &Item) -> Option<u64> {
+        let kind = profile.kind_of(&it.name);
+        self.policy.check(&kind, it).ok()?;
+        Some(self.pricing.price(&kind, it.quality))
+    }
+}
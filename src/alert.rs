@@ -0,0 +1,274 @@
+use crate::gilded_rose::GildedRose;
+use crate::json::Json;
+use crate::spec::{Kind, QUALITY_MAX};
+use std::fmt::{self, Display};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, TcpStream};
+use std::path::PathBuf;
+use std::time::Duration;
+
+// A condition checked against every non-legendary item after `update_quality`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AlertRule {
+    // Not expired yet, but `sell_in <= days`.
+    ExpiresWithin { days: i32 },
+    QualityBelow { quality: i32 },
+    // A Backstage pass whose concert is at most `days` away (day 0 is the concert).
+    ConcertWithin { days: i32 },
+    // Quality reached `QUALITY_MAX` and cannot grow any further.
+    AtCap,
+}
+
+impl AlertRule {
+    // Parse `expires:N`, `quality:N`, `concert:N` or `cap`.
+    pub fn parse(s: &str) -> Option<AlertRule> {
+        if s == "cap" {
+            return Some(AlertRule::AtCap);
+        }
+        let (kind, n) = s.split_once(':')?;
+        let n = n.parse().ok()?;
+        match kind {
+            "expires" => Some(AlertRule::ExpiresWithin { days: n }),
+            "quality" => Some(AlertRule::QualityBelow { quality: n }),
+            "concert" => Some(AlertRule::ConcertWithin { days: n }),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn fires(&self, kind: &Kind<'_>, sell_in: i32, quality: i32) -> bool {
+        if let Kind::Legendary(_) = kind {
+            return false;
+        }
+        match self {
+            AlertRule::ExpiresWithin { days } => (0..=*days).contains(&sell_in),
+            AlertRule::QualityBelow { quality: below } => quality < *below,
+            AlertRule::ConcertWithin { days } => {
+                matches!(kind, Kind::BackstagePass) && (0..=*days).contains(&sell_in)
+            }
+            AlertRule::AtCap => quality >= QUALITY_MAX,
+        }
+    }
+}
+
+impl Display for AlertRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertRule::ExpiresWithin { days } => write!(f, "expires within {} days", days),
+            AlertRule::QualityBelow { quality } => write!(f, "quality below {}", quality),
+            AlertRule::ConcertWithin { days } => write!(f, "concert within {} days", days),
+            AlertRule::AtCap => write!(f, "quality at cap"),
+        }
+    }
+}
+
+// One rule firing for one item; `sell_in` and `quality` are the values after the update.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Alert {
+    pub day: u32,
+    pub index: usize,
+    pub name: String,
+    pub sell_in: i32,
+    pub quality: i32,
+    pub rule: AlertRule,
+}
+
+impl Alert {
+    // `{"day":1,"index":0,"name":"...","sell_in":2,"quality":3,"rule":"..."}`
+    pub fn to_json(&self) -> Json {
+        Json::object([
+            ("day", self.day.into()),
+            ("index", self.index.into()),
+            ("name", self.name.as_str().into()),
+            ("sell_in", self.sell_in.into()),
+            ("quality", self.quality.into()),
+            ("rule", self.rule.to_string().into()),
+        ])
+    }
+}
+
+impl Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "day {}, {}, {}, {}: {}",
+            self.day, self.name, self.sell_in, self.quality, self.rule
+        )
+    }
+}
+
+// Where alerts go. A sink gets every batch, including empty ones, and decides what to skip.
+pub trait AlertSink {
+    fn deliver(&mut self, alerts: &[Alert]) -> io::Result<()>;
+}
+
+// One `alert: ...` line per alert to any writer (stdout in the CLI).
+pub struct WriterSink<W: Write> {
+    out: W,
+}
+
+impl<W: Write> WriterSink<W> {
+    pub fn new(out: W) -> WriterSink<W> {
+        WriterSink { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> AlertSink for WriterSink<W> {
+    fn deliver(&mut self, alerts: &[Alert]) -> io::Result<()> {
+        for alert in alerts {
+            writeln!(self.out, "alert: {}", alert)?;
+        }
+        self.out.flush()
+    }
+}
+
+// Appends one JSON object per line; the file is opened for every batch, so it can be rotated.
+pub struct FileSink {
+    path: PathBuf,
+}
+
+impl FileSink {
+    pub fn new(path: impl Into<PathBuf>) -> FileSink {
+        FileSink { path: path.into() }
+    }
+
+    fn open(&self) -> io::Result<File> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+    }
+}
+
+impl AlertSink for FileSink {
+    fn deliver(&mut self, alerts: &[Alert]) -> io::Result<()> {
+        if alerts.is_empty() {
+            return Ok(());
+        }
+        let mut file = self.open()?;
+        for alert in alerts {
+            writeln!(file, "{}", alert.to_json())?;
+        }
+        Ok(())
+    }
+}
+
+// POSTs each non-empty batch as a JSON array to `http://HOST:PORT/PATH`. Only loopback hosts
+// are accepted: this is for a local relay, not for sending stock data off the machine.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct WebhookSink {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl WebhookSink {
+    pub fn parse(url: &str) -> Option<WebhookSink> {
+        let rest = url.strip_prefix("http://")?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = authority.rsplit_once(':')?;
+        let loopback =
+            host == "localhost" || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback());
+        if !loopback {
+            return None;
+        }
+        Some(WebhookSink {
+            host: host.to_string(),
+            port: port.parse().ok()?,
+            path: path.to_string(),
+        })
+    }
+}
+
+impl AlertSink for WebhookSink {
+    fn deliver(&mut self, alerts: &[Alert]) -> io::Result<()> {
+        if alerts.is_empty() {
+            return Ok(());
+        }
+        let body = Json::Array(alerts.iter().map(Alert::to_json).collect()).to_string();
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port))?;
+        stream.set_read_timeout(Some(WEBHOOK_TIMEOUT))?;
+        stream.set_write_timeout(Some(WEBHOOK_TIMEOUT))?;
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.host,
+            self.port,
+            body.len(),
+            body
+        )?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        // "HTTP/1.1 204 No Content" -> "204"
+        let status = response.split(' ').nth(1).unwrap_or("");
+        if status.starts_with('2') {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!("webhook answered {:?}", status)))
+        }
+    }
+}
+
+// Alert rules plus the sinks they are delivered to.
+#[derive(Default)]
+pub struct Alerts {
+    pub rules: Vec<AlertRule>,
+    sinks: Vec<Box<dyn AlertSink>>,
+}
+
+impl Alerts {
+    pub fn new(rules: Vec<AlertRule>) -> Alerts {
+        Alerts {
+            rules,
+            sinks: Vec::new(),
+        }
+    }
+
+    pub fn add_sink(&mut self, sink: Box<dyn AlertSink>) {
+        self.sinks.push(sink);
+    }
+
+    // Every rule against every item, in inventory order; call after `update_quality`.
+    pub fn evaluate(&self, rose: &GildedRose) -> Vec<Alert> {
+        let mut alerts = Vec::new();
+        for (index, it) in rose.items.iter().enumerate() {
+            let kind = rose.profile().kind_of(&it.name);
+            for rule in &self.rules {
+                if rule.fires(&kind, it.sell_in, it.quality) {
+                    alerts.push(Alert {
+                        day: rose.day(),
+                        index,
+                        name: it.name.clone(),
+                        sell_in: it.sell_in,
+                        quality: it.quality,
+                        rule: *rule,
+                    });
+                }
+            }
+        }
+        alerts
+    }
+
+    // Evaluate and deliver to every sink. A failing sink does not stop the others; their
+    // errors are returned in sink order.
+    pub fn check(&mut self, rose: &GildedRose) -> (Vec<Alert>, Vec<io::Error>) {
+        let alerts = self.evaluate(rose);
+        let errors = self
+            .sinks
+            .iter_mut()
+            .filter_map(|sink| sink.deliver(&alerts).err())
+            .collect();
+        (alerts, errors)
+    }
+}
@@ -0,0 +1,211 @@
+use crate::appreciating::Appreciation;
+use crate::gilded_rose::Item;
+use crate::legendary::QUALITY_SULFURAS;
+use crate::pattern;
+use crate::profile::Profile;
+use crate::spec::Kind;
+use std::fmt::{self, Display};
+
+// Another name for a known item: a supplier's spelling or a translation. `pattern` is an
+// exact base name or a glob (see `pattern.rs`); `language` is the `[section]` it came from.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Alias {
+    pub pattern: String,
+    pub language: Option<String>,
+    pub target: AliasTarget,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AliasTarget {
+    // Classified as if it had this base name, e.g. "Aged Brie".
+    Name(String),
+    // Classified as this kind directly.
+    Kind(AliasKind),
+}
+
+// The kinds an alias can name without a registry entry of its own.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AliasKind {
+    Normal,
+    Backstage,
+    Legendary(i32),
+    // Appreciates like Aged Brie.
+    Appreciating,
+}
+
+impl AliasKind {
+    // Parse `normal`, `backstage`, `legendary[:QUALITY]` (80 by default) or `appreciating`.
+    pub fn parse(s: &str) -> Option<AliasKind> {
+        match s.split_once(':') {
+            Some(("legendary", q)) => Some(AliasKind::Legendary(q.parse().ok()?)),
+            Some(_) => None,
+            None => match s {
+                "normal" => Some(AliasKind::Normal),
+                "backstage" => Some(AliasKind::Backstage),
+                "legendary" => Some(AliasKind::Legendary(QUALITY_SULFURAS)),
+                "appreciating" => Some(AliasKind::Appreciating),
+                _ => None,
+            },
+        }
+    }
+
+    #[must_use]
+    pub const fn kind(self) -> Kind<'static> {
+        match self {
+            AliasKind::Normal => Kind::Normal,
+            AliasKind::Backstage => Kind::BackstagePass,
+            AliasKind::Legendary(quality) => Kind::Legendary(quality),
+            AliasKind::Appreciating => Kind::Appreciating(Appreciation::AGED_BRIE),
+        }
+    }
+}
+
+impl Display for AliasTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AliasTarget::Name(name) => write!(f, "{}", name),
+            AliasTarget::Kind(AliasKind::Normal) => write!(f, "kind:normal"),
+            AliasTarget::Kind(AliasKind::Backstage) => write!(f, "kind:backstage"),
+            AliasTarget::Kind(AliasKind::Legendary(q)) => write!(f, "kind:legendary:{}", q),
+            AliasTarget::Kind(AliasKind::Appreciating) => write!(f, "kind:appreciating"),
+        }
+    }
+}
+
+impl Alias {
+    #[must_use]
+    pub fn matches(&self, base_name: &str) -> bool {
+        pattern::matches(&self.pattern, base_name)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AliasError {
+    // The file could not be read.
+    Io(String),
+    // 1-based line number.
+    Syntax { line: usize, message: String },
+}
+
+impl Display for AliasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AliasError::Io(message) => write!(f, "cannot read aliases: {}", message),
+            AliasError::Syntax { line, message } => {
+                write!(f, "aliases line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+// Parse an alias table:
+//
+//     # Supplier spellings apply in every language.
+//     Brie, aged = Aged Brie
+//     [fr]
+//     Brie affiné = Aged Brie
+//     Billets de concert * = kind:backstage
+//
+// One `ALIAS = TARGET` per line, split at the last `=`, both sides trimmed. A target is a
+// base name or `kind:KIND` (see `AliasKind::parse`). `[LANG]` starts a language section;
+// `#` starts a comment line. An alias may be defined only once.
+pub fn parse(src: &str) -> Result<Vec<Alias>, AliasError> {
+    let mut aliases: Vec<Alias> = Vec::new();
+    let mut language = None;
+    for (i, line) in src.lines().enumerate() {
+        let err = |message: &str| AliasError::Syntax {
+            line: i + 1,
+            message: message.to_string(),
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[') {
+            let lang = section.strip_suffix(']').map(str::trim);
+            match lang {
+                Some(lang) if !lang.is_empty() => language = Some(lang.to_string()),
+                _ => return Err(err("expected [LANGUAGE]")),
+            }
+            continue;
+        }
+        let Some((alias, target)) = line.rsplit_once('=') else {
+            return Err(err("expected ALIAS = TARGET"));
+        };
+        let (alias, target) = (alias.trim(), target.trim());
+        if alias.is_empty() || target.is_empty() {
+            return Err(err("expected ALIAS = TARGET"));
+        }
+        if aliases.iter().any(|a| a.pattern == alias) {
+            return Err(err("alias defined twice"));
+        }
+        let target = match target.strip_prefix("kind:") {
+            Some(kind) => AliasTarget::Kind(AliasKind::parse(kind).ok_or_else(|| {
+                err("unknown kind; expected normal, backstage, legendary[:N] or appreciating")
+            })?),
+            None => AliasTarget::Name(target.to_string()),
+        };
+        aliases.push(Alias {
+            pattern: alias.to_string(),
+            language: language.clone(),
+            target,
+        });
+    }
+    Ok(aliases)
+}
+
+pub fn load(path: &str) -> Result<Vec<Alias>, AliasError> {
+    let src = std::fs::read_to_string(path).map_err(|e| AliasError::Io(e.to_string()))?;
+    parse(&src)
+}
+
+// One alias that some items were classified through.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AliasUse {
+    pub alias: Alias,
+    // Indexes of the items whose base name it matched.
+    pub items: Vec<usize>,
+}
+
+impl Display for AliasUse {
+    // `alias, language, target, items`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, {}, {},",
+            self.alias.pattern,
+            self.alias.language.as_deref().unwrap_or("-"),
+            self.alias.target
+        )?;
+        for i in &self.items {
+            write!(f, " #{}", i)?;
+        }
+        Ok(())
+    }
+}
+
+// The aliases `items` are classified through under `profile`, in table order.
+pub fn usage(profile: &Profile, items: &[Item]) -> Vec<AliasUse> {
+    let mut uses: Vec<AliasUse> = Vec::new();
+    for (index, it) in items.iter().enumerate() {
+        let (_, base_name) = profile.split_modifiers(&it.name);
+        let Some(alias) = profile.alias(base_name) else {
+            continue;
+        };
+        match uses.iter_mut().find(|u| u.alias.pattern == alias.pattern) {
+            Some(u) => u.items.push(index),
+            None => uses.push(AliasUse {
+                alias: alias.clone(),
+                items: vec![index],
+            }),
+        }
+    }
+    let order = |u: &AliasUse| {
+        profile
+            .aliases
+            .iter()
+            .position(|a| a.pattern == u.alias.pattern)
+    };
+    uses.sort_by_key(order);
+    uses
+}
@@ -0,0 +1,89 @@
+use crate::pattern;
+use std::borrow::Cow;
+
+pub const NAME_BRIE: &str = "Aged Brie";
+
+// How an appreciating good (Aged Brie, wines, cheeses) gains quality.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Appreciation {
+    // Gain per day before the sell-by date.
+    pub rate: i32,
+    pub after_expiry: AfterExpiry,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AfterExpiry {
+    // Keep gaining `rate`, plus `extra` in the expiry pass (Aged Brie: +1 + 1).
+    Continue { extra: i32 },
+    // Stop gaining once the sell-by date is reached; quality holds.
+    Plateau,
+    // Stop gaining and lose `rate` per day instead.
+    Spoil { rate: i32 },
+}
+
+impl Appreciation {
+    pub const AGED_BRIE: Appreciation = Appreciation {
+        rate: 1,
+        after_expiry: AfterExpiry::Continue { extra: 1 },
+    };
+
+    // Parse `RATE[:continue:N|:plateau|:spoil:N]`; a bare rate keeps gaining at that rate.
+    pub fn parse(s: &str) -> Option<Appreciation> {
+        let (rate, after) = match s.split_once(':') {
+            Some((rate, after)) => (rate, Some(after)),
+            None => (s, None),
+        };
+        let rate = rate.parse().ok().filter(|r: &i32| *r >= 0)?;
+        let non_negative = |n: &str| n.parse().ok().filter(|n: &i32| *n >= 0);
+        let after_expiry = match after.map(|a| a.split_once(':').unwrap_or((a, ""))) {
+            None => AfterExpiry::Continue { extra: 0 },
+            Some(("plateau", "")) => AfterExpiry::Plateau,
+            Some(("continue", n)) => AfterExpiry::Continue {
+                extra: non_negative(n)?,
+            },
+            Some(("spoil", n)) => AfterExpiry::Spoil {
+                rate: non_negative(n)?,
+            },
+            Some(_) => return None,
+        };
+        Some(Appreciation { rate, after_expiry })
+    }
+}
+
+// Registry entry: base names matching `pattern` appreciate according to `rule`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Appreciating {
+    pub pattern: Cow<'static, str>,
+    pub rule: Appreciation,
+}
+
+impl Appreciating {
+    pub const fn new(pattern: &'static str, rule: Appreciation) -> Appreciating {
+        Appreciating {
+            pattern: Cow::Borrowed(pattern),
+            rule,
+        }
+    }
+
+    // Parse `PATTERN=RULE`, see `Appreciation::parse`.
+    pub fn parse(spec: &str) -> Option<Appreciating> {
+        let (pattern, rule) = spec.rsplit_once('=')?;
+        if pattern.is_empty() {
+            return None;
+        }
+        Some(Appreciating {
+            pattern: Cow::Owned(pattern.to_string()),
+            rule: Appreciation::parse(rule)?,
+        })
+    }
+
+    #[must_use]
+    pub fn matches(&self, name: &str) -> bool {
+        pattern::matches(&self.pattern, name)
+    }
+}
+
+// Default registry: the kata's only appreciating good.
+pub fn builtin_appreciating() -> Vec<Appreciating> {
+    vec![Appreciating::new(NAME_BRIE, Appreciation::AGED_BRIE)]
+}
@@ -0,0 +1,56 @@
+use crate::modifier::Modifiers;
+use crate::profile::Profile;
+use crate::spec::{QUALITY_MAX, QUALITY_MIN};
+use std::ops::RangeInclusive;
+
+// What a custom kind gets to see besides the item's own values.
+#[derive(Debug, Clone, Copy)]
+pub struct ItemContext<'a> {
+    // `GildedRose::day()` before the update.
+    pub day: u32,
+    // The name without modifier prefixes, as `classify` saw it.
+    pub base_name: &'a str,
+    // Scale changes with `mods.degrade(n)` / `mods.appreciate(n)` to honour Conjured & co.
+    pub mods: Modifiers,
+    pub profile: &'a Profile,
+}
+
+// A kind defined outside this crate, registered with `GildedRose::add_behavior`.
+//
+// Registered behaviors form a classifier chain that is asked before the built-in kinds (even
+// before the legendary registry); the first one whose `classify` accepts the base name owns
+// the item. Each update is `daily`, then `next_sell_in`, then `expiry` once `sell_in` is
+// negative, the same order as the built-in pipeline. Quality is clamped to `quality_bounds`
+// (default `QUALITY_MIN..=QUALITY_MAX`) after `daily` and after `expiry`, so a behavior cannot
+// break its own invariants; clamping is reported to observers like for built-in kinds.
+pub trait ItemBehavior {
+    // Whether this behavior owns items with this base name.
+    fn classify(&self, base_name: &str) -> bool;
+
+    // The new quality for today, before `sell_in` moves.
+    fn daily(&self, ctx: &ItemContext<'_>, sell_in: i32, quality: i32) -> i32;
+
+    // The new quality once the sell-by date has passed; `sell_in` is already decremented.
+    fn expiry(&self, _ctx: &ItemContext<'_>, _sell_in: i32, quality: i32) -> i32 {
+        quality
+    }
+
+    // Override to keep `sell_in` fixed (e.g. a custom legendary kind).
+    fn next_sell_in(&self, ctx: &ItemContext<'_>, sell_in: i32) -> i32 {
+        ctx.profile.next_sell_in(sell_in)
+    }
+
+    // The range quality is clamped to; a custom legendary kind would return `80..=80`.
+    fn quality_bounds(&self, _ctx: &ItemContext<'_>) -> RangeInclusive<i32> {
+        QUALITY_MIN..=QUALITY_MAX
+    }
+
+    // Reject items this kind cannot hold, with a reason; see `GildedRose::validate`.
+    fn validate(&self, ctx: &ItemContext<'_>, _sell_in: i32, quality: i32) -> Result<(), String> {
+        if self.quality_bounds(ctx).contains(&quality) {
+            Ok(())
+        } else {
+            Err(format!("quality out of range: {}", quality))
+        }
+    }
+}
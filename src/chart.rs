@@ -0,0 +1,258 @@
+use crate::gilded_rose::GildedRose;
+use crate::pattern;
+use crate::render::html_escape;
+use crate::spec::QUALITY_MAX;
+use std::fmt::Write;
+
+// Which lines to draw.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Selection {
+    // One line per item whose name matches any of the globs.
+    Items(Vec<String>),
+    // One line per kind: the mean quality of its items.
+    Kinds,
+}
+
+// Quality by day for one item or kind.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Series {
+    pub label: String,
+    pub kind: &'static str,
+    // `(day, quality)`, one point per day.
+    pub points: Vec<(u32, i32)>,
+    // First day with `sell_in <= 0`, i.e. the last day before the item expires.
+    pub sell_by: Option<u32>,
+    // First day a Backstage pass fell from a positive quality to 0.
+    pub drop: Option<u32>,
+}
+
+// Track every item for `days` updates; entry `i` is `rose.items[i]`, with `days + 1` points.
+pub fn trajectories(rose: &mut GildedRose, days: u32) -> Vec<Series> {
+    let mut series: Vec<Series> = rose
+        .items
+        .iter()
+        .enumerate()
+        .map(|(i, it)| Series {
+            label: format!("#{} {}", i, it.name),
+            kind: rose.kind_label(&it.name),
+            points: Vec::new(),
+            sell_by: None,
+            drop: None,
+        })
+        .collect();
+    for step in 0..=days {
+        let day = rose.day();
+        for (s, it) in series.iter_mut().zip(&rose.items) {
+            if s.sell_by.is_none() && s.kind != "legendary" && it.sell_in <= 0 {
+                s.sell_by = Some(day);
+            }
+            let fell = s.points.last().is_some_and(|&(_, q)| q > 0) && it.quality == 0;
+            if s.drop.is_none() && s.kind == "backstage" && fell {
+                s.drop = Some(day);
+            }
+            s.points.push((day, it.quality));
+        }
+        if step < days {
+            rose.update_quality();
+        }
+    }
+    series
+}
+
+// Keep the series `selection` asks for; `Kinds` averages each kind's items (rounded down)
+// and keeps the earliest markers of its members.
+pub fn select(series: Vec<Series>, selection: &Selection) -> Vec<Series> {
+    let globs = match selection {
+        Selection::Items(globs) => globs,
+        Selection::Kinds => return by_kind(series),
+    };
+    series
+        .into_iter()
+        .filter(|s| {
+            let name = s.label.split_once(' ').map_or("", |(_, name)| name);
+            globs.iter().any(|g| pattern::matches(g, name))
+        })
+        .collect()
+}
+
+fn by_kind(series: Vec<Series>) -> Vec<Series> {
+    let mut kinds: Vec<(&'static str, Vec<Series>)> = Vec::new();
+    for s in series {
+        match kinds.binary_search_by_key(&s.kind, |(k, _)| k) {
+            Ok(i) => kinds[i].1.push(s),
+            Err(i) => kinds.insert(i, (s.kind, vec![s])),
+        }
+    }
+    kinds
+        .into_iter()
+        .map(|(kind, members)| {
+            let n = members.len() as i64;
+            let points = (0..members[0].points.len())
+                .map(|p| {
+                    let sum: i64 = members.iter().map(|m| i64::from(m.points[p].1)).sum();
+                    let mean = i32::try_from(sum.div_euclid(n)).unwrap_or(i32::MAX);
+                    (members[0].points[p].0, mean)
+                })
+                .collect();
+            Series {
+                label: format!("{} ({})", kind, n),
+                kind,
+                points,
+                sell_by: members.iter().filter_map(|m| m.sell_by).min(),
+                drop: members.iter().filter_map(|m| m.drop).min(),
+            }
+        })
+        .collect()
+}
+
+const WIDTH: i64 = 1000;
+const HEIGHT: i64 = 420;
+const LEFT: i64 = 50;
+const RIGHT: i64 = 330;
+const TOP: i64 = 40;
+const BOTTOM: i64 = 40;
+const COLOURS: [&str; 8] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+];
+
+// A standalone SVG line chart: quality against day, a legend on the right, a hollow circle
+// on each sell-by day and a filled red circle where a Backstage pass dropped to 0.
+pub fn svg(title: &str, series: &[Series]) -> String {
+    let first = series
+        .iter()
+        .flat_map(|s| s.points.first())
+        .map(|p| p.0)
+        .min()
+        .unwrap_or(0);
+    let last = series
+        .iter()
+        .flat_map(|s| s.points.last())
+        .map(|p| p.0)
+        .max()
+        .unwrap_or(0)
+        .max(first + 1);
+    let top_quality = series
+        .iter()
+        .flat_map(|s| &s.points)
+        .map(|p| p.1)
+        .max()
+        .unwrap_or(0)
+        .max(QUALITY_MAX);
+    let (plot_w, plot_h) = (WIDTH - LEFT - RIGHT, HEIGHT - TOP - BOTTOM);
+    let x =
+        |day: u32| LEFT + (i64::from(day) - i64::from(first)) * plot_w / i64::from(last - first);
+    let y = |q: i32| TOP + plot_h - i64::from(q.max(0)) * plot_h / i64::from(top_quality);
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"11\">",
+        w = WIDTH,
+        h = HEIGHT
+    );
+    let _ = writeln!(out, "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>");
+    let _ = writeln!(
+        out,
+        "<text x=\"{}\" y=\"20\" font-size=\"14\">{}</text>",
+        LEFT,
+        html_escape(title)
+    );
+    // Axes, with grid lines every 10 quality points and every 5 days.
+    for q in (0..=top_quality).step_by(10) {
+        let _ = writeln!(
+            out,
+            "<line x1=\"{l}\" y1=\"{y}\" x2=\"{r}\" y2=\"{y}\" stroke=\"#eee\"/><text x=\"{t}\" y=\"{y}\" text-anchor=\"end\" dy=\"4\">{q}</text>",
+            l = LEFT,
+            r = LEFT + plot_w,
+            t = LEFT - 6,
+            y = y(q),
+            q = q
+        );
+    }
+    for day in (first..=last).filter(|d| (d - first) % 5 == 0) {
+        let _ = writeln!(
+            out,
+            "<line x1=\"{x}\" y1=\"{t}\" x2=\"{x}\" y2=\"{b}\" stroke=\"#eee\"/><text x=\"{x}\" y=\"{l}\" text-anchor=\"middle\">{d}</text>",
+            x = x(day),
+            t = TOP,
+            b = TOP + plot_h,
+            l = TOP + plot_h + 16,
+            d = day
+        );
+    }
+    let _ = writeln!(
+        out,
+        "<polyline points=\"{l},{t} {l},{b} {r},{b}\" fill=\"none\" stroke=\"black\"/>",
+        l = LEFT,
+        t = TOP,
+        b = TOP + plot_h,
+        r = LEFT + plot_w
+    );
+    let _ = writeln!(
+        out,
+        "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">day</text>",
+        LEFT + plot_w / 2,
+        HEIGHT - 6
+    );
+    let _ = writeln!(
+        out,
+        "<text x=\"12\" y=\"{}\" transform=\"rotate(-90 12 {})\" text-anchor=\"middle\">quality</text>",
+        TOP + plot_h / 2,
+        TOP + plot_h / 2
+    );
+
+    for (i, s) in series.iter().enumerate() {
+        let colour = COLOURS[i % COLOURS.len()];
+        let label = html_escape(&s.label);
+        let points: Vec<String> = s
+            .points
+            .iter()
+            .map(|&(d, q)| format!("{},{}", x(d), y(q)))
+            .collect();
+        let _ = writeln!(
+            out,
+            "<polyline class=\"series\" points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"><title>{}</title></polyline>",
+            points.join(" "),
+            colour,
+            label
+        );
+        let at = |day: u32| {
+            s.points
+                .iter()
+                .find(|p| p.0 == day)
+                .map(|p| (x(p.0), y(p.1)))
+        };
+        if let Some(day) = s.sell_by
+            && let Some((cx, cy)) = at(day)
+        {
+            let _ = writeln!(
+                out,
+                "<circle class=\"sell-by\" cx=\"{}\" cy=\"{}\" r=\"5\" fill=\"white\" stroke=\"{}\" stroke-width=\"2\"><title>{}: sell by day {}</title></circle>",
+                cx, cy, colour, label, day
+            );
+        }
+        if let Some(day) = s.drop
+            && let Some((cx, cy)) = at(day)
+        {
+            let _ = writeln!(
+                out,
+                "<circle class=\"drop\" cx=\"{}\" cy=\"{}\" r=\"5\" fill=\"#d62728\"><title>{}: dropped to 0 on day {}</title></circle>",
+                cx, cy, label, day
+            );
+        }
+        let ly = TOP + 16 * i as i64;
+        let lx = WIDTH - RIGHT + 20;
+        let _ = writeln!(
+            out,
+            "<line x1=\"{}\" y1=\"{ly}\" x2=\"{}\" y2=\"{ly}\" stroke=\"{}\" stroke-width=\"2\"/><text x=\"{}\" y=\"{ly}\" dy=\"4\">{}</text>",
+            lx,
+            lx + 16,
+            colour,
+            lx + 22,
+            label,
+            ly = ly
+        );
+    }
+    out.push_str("</svg>\n");
+    out
+}
@@ -0,0 +1,197 @@
+use crate::alert::{AlertRule, WebhookSink};
+use crate::appreciating::Appreciating;
+use crate::chart::Selection;
+use crate::decay::Perishable;
+use crate::demand::{DemandRule, Preference};
+use crate::diff;
+use crate::expr::{ExprRule, ParseError};
+use crate::legendary::Legendary;
+use crate::modifier;
+use crate::profile::{ConjuredPolicy, Profile};
+use crate::render::Format;
+use crate::supply::{Delivery, ReorderRule, Supplier, Supply};
+use std::fmt::{self, Display};
+
+pub const USAGE: &str = "usage: gilded-rose [--profile default|classic] \
+                         [--conjured ignored|degrade-only[:N]|all-changes[:N]] \
+                         [--modifier blessed|cursed|enchanted]... [--legendary PATTERN=QUALITY]... \
+                         [--appreciating PATTERN=RATE[:plateau|:continue:N|:spoil:N]]... \
+                         [--decay PATTERN=linear:N|percent:P[:MIN]|stepped:AT:RATE,..|table:SELL_IN:RATE,..]... \
+                         [--event EVENT=DAYS]... [--postpone EVENT=DAYS@DAY]... [--cancel EVENT@DAY]... \
+                         [--sell NAME@DAY]... [--deliver NAME=SELL_IN,QUALITY[,COUNT]@DAY]... \
+                         [--supplier SUPPLIER=LEAD_TIME]... \
+                         [--reorder NAME=SELL_IN,QUALITY[,COUNT]<THRESHOLD[/GLOB|/kind:KIND]@SUPPLIER]... \
+                         [--simulate TRIALS@SEED] [--demand PATTERN=MEAN|kind:KIND=MEAN]... [--prefer quality|price] \
+                         [--advise HORIZON] [--alert expires:N|quality:N|concert:N|cap]... \
+                         [--alert-file PATH] [--alert-webhook http://127.0.0.1:PORT/PATH] \
+                         [--rule PATTERN=DAILY_EXPR[;EXPIRY_EXPR]]... [--serve ADDR] [--rpc stdio] [--repl stdio] [--report md|html] [--chart kinds|NAME_GLOB]... [--diff text|json] [--aliases PATH]";
+
+// Parsed command line. `profile` stays `None` unless a flag asked for something non-default.
+#[derive(Debug, Default)]
+pub struct Options {
+    pub profile: Option<Profile>,
+    pub events: Vec<(String, i32)>,
+    // Applied at the start of the given simulation day, before the listing is printed.
+    pub event_actions: Vec<(u32, EventAction)>,
+    // Sell the first item with this exact name on the given day, after the listing.
+    pub sales: Vec<(u32, String)>,
+    // Deliveries, suppliers and reorder rules; received at the start of each day.
+    pub supply: Supply,
+    // Run a Monte Carlo demand simulation with `(trials, seed)` instead of the day listing.
+    pub simulate: Option<(u32, u64)>,
+    pub demand: Vec<DemandRule>,
+    pub preference: Preference,
+    // Print the best sell day of every item within this many days instead of the day listing.
+    pub advise: Option<u32>,
+    // Checked after every update; printed, and also sent to the file and webhook if given.
+    pub alerts: Vec<AlertRule>,
+    pub alert_file: Option<String>,
+    pub alert_webhook: Option<WebhookSink>,
+    // Expression rules, registered as one custom kind ahead of the built-in ones.
+    pub rules: Vec<ExprRule>,
+    // Serve the inventory over HTTP on this address instead of the day listing.
+    pub serve: Option<String>,
+    // Answer line-delimited JSON-RPC on stdin/stdout instead of the day listing.
+    pub rpc: bool,
+    // Start the interactive console on the sample stock instead of the day listing.
+    pub repl: bool,
+    // Print the day listings as a Markdown or HTML report instead of the plain listing.
+    pub report: Option<Format>,
+    // Print an SVG chart of quality by day instead of the plain listing.
+    pub chart: Option<Selection>,
+    // Print what changed from each day to the next instead of the plain listing.
+    pub diff: Option<diff::Format>,
+    // Alias table to load into the profile before anything is classified.
+    pub aliases: Option<String>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum EventAction {
+    Postpone { event: String, days: u32 },
+    Cancel { event: String },
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CliError {
+    // An unknown flag or malformed value; the caller prints `USAGE`.
+    Usage,
+    // A `--rule` that does not parse; the error's offset is into `spec`.
+    Rule { spec: String, error: ParseError },
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage => write!(f, "{}", USAGE),
+            CliError::Rule { spec, error } => write!(f, "rule {:?}: {}", spec, error),
+        }
+    }
+}
+
+impl Options {
+    fn profile_mut(&mut self) -> &mut Profile {
+        self.profile.get_or_insert_with(Profile::default)
+    }
+}
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, CliError> {
+    let mut opts = Options::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or(CliError::Usage)?;
+        if arg == "--rule" {
+            let rule = ExprRule::parse(&value).map_err(|error| CliError::Rule {
+                spec: value.clone(),
+                error,
+            })?;
+            opts.rules.push(rule);
+        } else {
+            apply(&mut opts, &arg, value).ok_or(CliError::Usage)?;
+        }
+    }
+    Ok(opts)
+}
+
+// One flag other than `--rule`; `None` on an unknown flag or malformed value.
+fn apply(opts: &mut Options, arg: &str, value: String) -> Option<()> {
+    match arg {
+        // Replaces the whole profile, so it may only come once and before any flag that
+        // adjusts it (`--conjured`, `--modifier`, `--legendary`, ...).
+        "--profile" if opts.profile.is_some() => return None,
+        "--profile" => opts.profile = Some(Profile::by_name(&value)?),
+        // Applied on top of `--profile`.
+        "--conjured" => opts.profile_mut().conjured = ConjuredPolicy::parse(&value)?,
+        "--modifier" => {
+            let m = modifier::by_name(&value)?;
+            let modifiers = &mut opts.profile_mut().modifiers;
+            if !modifiers.contains(&m) {
+                modifiers.push(m);
+            }
+        }
+        "--legendary" => opts
+            .profile_mut()
+            .legendaries
+            .push(Legendary::parse(&value)?),
+        "--appreciating" => opts
+            .profile_mut()
+            .appreciating
+            .push(Appreciating::parse(&value)?),
+        "--decay" => opts
+            .profile_mut()
+            .perishables
+            .push(Perishable::parse(&value)?),
+        "--event" => {
+            let (event, days) = value.rsplit_once('=')?;
+            opts.events.push((event.to_string(), days.parse().ok()?));
+        }
+        "--postpone" => {
+            let (event, rest) = value.rsplit_once('=')?;
+            let (days, day) = rest.split_once('@')?;
+            let action = EventAction::Postpone {
+                event: event.to_string(),
+                days: days.parse().ok()?,
+            };
+            opts.event_actions.push((day.parse().ok()?, action));
+        }
+        "--cancel" => {
+            let (event, day) = value.rsplit_once('@')?;
+            let action = EventAction::Cancel {
+                event: event.to_string(),
+            };
+            opts.event_actions.push((day.parse().ok()?, action));
+        }
+        "--sell" => {
+            let (name, day) = value.rsplit_once('@')?;
+            opts.sales.push((day.parse().ok()?, name.to_string()));
+        }
+        "--deliver" => opts.supply.schedule(Delivery::parse(&value)?),
+        "--supplier" => opts.supply.add_supplier(Supplier::parse(&value)?),
+        // The supplier must have been declared with an earlier `--supplier`.
+        "--reorder" => opts.supply.add_rule(ReorderRule::parse(&value)?).ok()?,
+        "--simulate" => {
+            let (trials, seed) = value.split_once('@')?;
+            opts.simulate = Some((trials.parse().ok()?, seed.parse().ok()?));
+        }
+        "--demand" => opts.demand.push(DemandRule::parse(&value)?),
+        "--prefer" => opts.preference = Preference::parse(&value)?,
+        "--advise" => opts.advise = Some(value.parse().ok()?),
+        "--alert" => opts.alerts.push(AlertRule::parse(&value)?),
+        "--alert-file" => opts.alert_file = Some(value),
+        "--alert-webhook" => opts.alert_webhook = Some(WebhookSink::parse(&value)?),
+        "--serve" => opts.serve = Some(value),
+        // Takes a value like every other flag; stdio is the only transport so far.
+        "--rpc" if value == "stdio" => opts.rpc = true,
+        "--repl" if value == "stdio" => opts.repl = true,
+        "--report" => opts.report = Some(Format::parse(&value)?),
+        "--chart" if value == "kinds" => opts.chart = Some(Selection::Kinds),
+        // Repeat to chart several items; `kinds` replaces any globs given before.
+        "--chart" => match &mut opts.chart {
+            Some(Selection::Items(globs)) => globs.push(value),
+            _ => opts.chart = Some(Selection::Items(vec![value])),
+        },
+        "--diff" => opts.diff = Some(diff::Format::parse(&value)?),
+        "--aliases" => opts.aliases = Some(value),
+        _ => return None,
+    }
+    Some(())
+}
@@ -0,0 +1,478 @@
+use crate::explain::Explanation;
+use crate::gilded_rose::{GildedRose, Item};
+use crate::lint;
+use crate::pattern;
+use crate::supply::ItemSpec;
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::process::{Command, Stdio};
+
+pub const CONSOLE_HELP: &str = "\
+commands:
+  show                          list every item
+  filter GLOB | kind:LABEL      list matching items
+  add NAME=SELL_IN,QUALITY[,N]  add N copies (default 1, at most 1000)
+  rm ITEM                       remove an item (ITEM is an index or an exact name)
+  edit ITEM FIELD=VALUE         set name, sell_in or quality
+  tick [N]                      advance N days (default 1, at most 3650)
+  explain ITEM                  why the item changed at the last tick
+  undo                          revert the last add, rm, edit or tick
+  history                       list the commands entered so far
+  help, quit";
+
+const COMMANDS: [&str; 11] = [
+    "add", "edit", "explain", "filter", "help", "history", "quit", "rm", "show", "tick", "undo",
+];
+
+// Upper bounds for one `tick` and one `add`, so a typo cannot hang the console.
+const MAX_TICKS: u32 = 3_650;
+const MAX_COPIES: u32 = 1_000;
+
+// A change to the inventory, journaled for `undo`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Op {
+    Add(ItemSpec),
+    Remove(usize),
+    Edit(usize, Field),
+    Tick(u32),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Field {
+    Name(String),
+    SellIn(i32),
+    Quality(i32),
+}
+
+// An interactive session over a `GildedRose` built by `make`, as in `rpc::Session`.
+//
+// Undo replays the journal minus its last entry onto a fresh inventory, so the day counter,
+// events and custom kinds come back exactly as they were.
+pub struct Console<F: Fn(Vec<Item>) -> GildedRose> {
+    make: F,
+    initial: Vec<Item>,
+    journal: Vec<Op>,
+    rose: GildedRose,
+    // What the last tick did to each item; `None` for items added or edited since.
+    last_update: Vec<Option<Explanation>>,
+    history: Vec<String>,
+}
+
+impl<F: Fn(Vec<Item>) -> GildedRose> Console<F> {
+    pub fn new(make: F, items: Vec<Item>) -> Console<F> {
+        let last_update = vec![None; items.len()];
+        Console {
+            rose: make(copy(&items)),
+            make,
+            initial: items,
+            journal: Vec::new(),
+            last_update,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn rose(&self) -> &GildedRose {
+        &self.rose
+    }
+
+    // Run one command line and return what to print; errors are for the user, not fatal.
+    pub fn execute(&mut self, line: &str) -> Result<String, String> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(String::new());
+        }
+        self.history.push(line.to_string());
+        let (command, arg) = line.split_once(' ').unwrap_or((line, ""));
+        let arg = arg.trim();
+        match command {
+            "show" => Ok(self.table(|_| true)),
+            "filter" => match arg.strip_prefix("kind:") {
+                Some(label) => Ok(self.table(|it| self.rose.kind_label(&it.name) == label)),
+                None if !arg.is_empty() => Ok(self.table(|it| pattern::matches(arg, &it.name))),
+                None => Err("usage: filter GLOB | kind:LABEL".into()),
+            },
+            "add" => match ItemSpec::parse(arg).filter(|s| (1..=MAX_COPIES).contains(&s.count)) {
+                Some(spec) => self.apply(Op::Add(spec)),
+                None => Err(format!(
+                    "usage: add NAME=SELL_IN,QUALITY[,N], N in 1..={}",
+                    MAX_COPIES
+                )),
+            },
+            "rm" => {
+                let index = self.item(arg)?;
+                self.apply(Op::Remove(index))
+            }
+            "edit" => {
+                // Split at the first ` FIELD=`, so a new name may contain spaces.
+                let split = ["name", "sell_in", "quality"]
+                    .iter()
+                    .filter_map(|f| arg.find(&format!(" {}=", f)))
+                    .min()
+                    .ok_or("usage: edit ITEM FIELD=VALUE")?;
+                let (item, field) = (&arg[..split], &arg[split + 1..]);
+                let index = self.item(item)?;
+                self.apply(Op::Edit(index, parse_field(field)?))
+            }
+            "tick" => {
+                let days = if arg.is_empty() {
+                    Some(1)
+                } else {
+                    arg.parse().ok()
+                };
+                match days.filter(|d| (1..=MAX_TICKS).contains(d)) {
+                    Some(days) => self.apply(Op::Tick(days)),
+                    None => Err(format!("usage: tick [N], N in 1..={}", MAX_TICKS)),
+                }
+            }
+            "explain" => {
+                let index = self.item(arg)?;
+                Ok(match &self.last_update[index] {
+                    Some(e) => describe(e, "last tick"),
+                    None => Explanation::of(&self.rose, index)
+                        .map(|e| describe(&e, "not ticked since it changed; next tick"))
+                        .unwrap_or_default(),
+                })
+            }
+            "undo" => {
+                if self.journal.pop().is_none() {
+                    return Err("nothing to undo".into());
+                }
+                self.rebuild();
+                Ok(format!(
+                    "day {}, {} items",
+                    self.rose.day(),
+                    self.rose.items.len()
+                ))
+            }
+            "history" => Ok(self
+                .history
+                .iter()
+                .enumerate()
+                .map(|(i, line)| format!("{:>4}  {}", i + 1, line))
+                .collect::<Vec<_>>()
+                .join("\n")),
+            "help" => Ok(CONSOLE_HELP.to_string()),
+            _ => Err(format!("unknown command: {} (try help)", command)),
+        }
+    }
+
+    // Completions for the whole line: command names for the first word, item names after
+    // commands that take an item or a glob.
+    pub fn complete(&self, line: &str) -> Vec<String> {
+        let Some((command, arg)) = line.split_once(' ') else {
+            return COMMANDS
+                .iter()
+                .filter(|c| c.starts_with(line))
+                .map(|c| format!("{} ", c))
+                .collect();
+        };
+        if !matches!(command, "rm" | "edit" | "explain" | "filter") {
+            return Vec::new();
+        }
+        let mut names: Vec<&str> = self
+            .rose
+            .items
+            .iter()
+            .map(|it| it.name.as_str())
+            .filter(|name| name.starts_with(arg))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+            .into_iter()
+            .map(|name| format!("{} {}", command, name))
+            .collect()
+    }
+
+    // Plain line-by-line loop for pipes and files; `quit` or end of input stops it.
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if !self.answer(&line, &mut output)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // Interactive loop with tab completion and arrow-key history when stdin is a terminal
+    // that `stty` can switch to non-canonical mode; `run` on stdin otherwise.
+    pub fn run_terminal(&mut self) -> io::Result<()> {
+        let stdin = io::stdin();
+        let Some(_raw) = stdin.is_terminal().then(RawMode::enable).flatten() else {
+            return self.run(stdin.lock(), io::stdout().lock());
+        };
+        let mut out = io::stdout().lock();
+        let mut input = stdin.lock();
+        while let Some(line) = self.read_line(&mut input, &mut out)? {
+            if !self.answer(&line, &mut out)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // Print the answer to one line; `false` once the user quits.
+    fn answer(&mut self, line: &str, out: &mut impl Write) -> io::Result<bool> {
+        if matches!(line.trim(), "quit" | "exit") {
+            return Ok(false);
+        }
+        match self.execute(line) {
+            Ok(text) if text.is_empty() => (),
+            Ok(text) => writeln!(out, "{}", text)?,
+            Err(e) => writeln!(out, "error: {}", e)?,
+        }
+        out.flush()?;
+        Ok(true)
+    }
+
+    // A minimal line editor: printable input, backspace, Tab, Up/Down through `history`,
+    // Enter. `None` on Ctrl-C, on Ctrl-D at an empty line or at end of input.
+    fn read_line(&self, input: &mut impl Read, out: &mut impl Write) -> io::Result<Option<String>> {
+        let mut buf: Vec<u8> = Vec::new();
+        let mut recall = self.history.len();
+        let redraw = |out: &mut dyn Write, buf: &[u8]| -> io::Result<()> {
+            write!(out, "\r\x1b[K> {}", String::from_utf8_lossy(buf))?;
+            out.flush()
+        };
+        redraw(out, &buf)?;
+        let mut byte = [0; 1];
+        loop {
+            if input.read(&mut byte)? == 0 {
+                return Ok(None);
+            }
+            match byte[0] {
+                b'\r' | b'\n' => {
+                    writeln!(out, "\r")?;
+                    return Ok(Some(String::from_utf8_lossy(&buf).into_owned()));
+                }
+                // Ctrl-C arrives as a byte (see `RawMode`); Ctrl-D only ends an empty line.
+                3 => {
+                    writeln!(out, "\r")?;
+                    return Ok(None);
+                }
+                4 if buf.is_empty() => {
+                    writeln!(out, "\r")?;
+                    return Ok(None);
+                }
+                // Backspace / DEL: drop one whole UTF-8 character.
+                8 | 127 => {
+                    while let Some(b) = buf.pop() {
+                        if b & 0xC0 != 0x80 {
+                            break;
+                        }
+                    }
+                }
+                b'\t' => {
+                    let line = String::from_utf8_lossy(&buf).into_owned();
+                    let candidates = self.complete(&line);
+                    let prefix = common_prefix(&candidates);
+                    if prefix.len() > line.len() {
+                        buf = prefix.into_bytes();
+                    } else if candidates.len() > 1 {
+                        write!(out, "\r\n{}\r\n", candidates.join("\r\n"))?;
+                    }
+                }
+                // Escape sequences: only Up (`ESC [ A`) and Down (`ESC [ B`) are used.
+                0x1b => {
+                    let mut seq = [0; 2];
+                    input.read_exact(&mut seq)?;
+                    match seq {
+                        [b'[', b'A'] if recall > 0 => recall -= 1,
+                        [b'[', b'B'] if recall < self.history.len() => recall += 1,
+                        _ => continue,
+                    }
+                    buf = self
+                        .history
+                        .get(recall)
+                        .map(|line| line.clone().into_bytes())
+                        .unwrap_or_default();
+                }
+                b if b >= 0x20 => buf.push(b),
+                _ => (),
+            }
+            redraw(out, &buf)?;
+        }
+    }
+
+    // `index` or an exact item name (the first item with it).
+    fn item(&self, arg: &str) -> Result<usize, String> {
+        let index = match arg.parse::<usize>() {
+            Ok(index) => Some(index).filter(|i| *i < self.rose.items.len()),
+            Err(_) => self.rose.items.iter().position(|it| it.name == arg),
+        };
+        index.ok_or_else(|| format!("no such item: {}", arg))
+    }
+
+    fn table(&self, keep: impl Fn(&Item) -> bool) -> String {
+        let mut lines = vec![format!(
+            "day {}: index, name, sellIn, quality",
+            self.rose.day()
+        )];
+        for (i, it) in self
+            .rose
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, it)| keep(it))
+        {
+            lines.push(format!("{:>3}  {}", i, it));
+        }
+        lines.join("\n")
+    }
+
+    // Apply and journal a validated change.
+    fn apply(&mut self, op: Op) -> Result<String, String> {
+        let message = self.perform(&op)?;
+        self.journal.push(op);
+        Ok(message)
+    }
+
+    fn perform(&mut self, op: &Op) -> Result<String, String> {
+        match op {
+            Op::Add(spec) => {
+                let item = Item::new(spec.name.clone(), spec.sell_in, spec.quality);
+                self.rose.validate_item(&item)?;
+                for _ in 0..spec.count {
+                    self.rose
+                        .items
+                        .push(Item::new(item.name.clone(), item.sell_in, item.quality));
+                    self.last_update.push(None);
+                }
+                Ok(format!("added {}{}", spec, self.warning(&item.name)))
+            }
+            Op::Remove(index) => {
+                let removed = self.rose.items.remove(*index);
+                self.last_update.remove(*index);
+                Ok(format!("removed {}", removed))
+            }
+            Op::Edit(index, field) => {
+                let current = &self.rose.items[*index];
+                let mut edited = Item::new(current.name.clone(), current.sell_in, current.quality);
+                match field {
+                    Field::Name(name) => edited.name = name.clone(),
+                    Field::SellIn(sell_in) => edited.sell_in = *sell_in,
+                    Field::Quality(quality) => edited.quality = *quality,
+                }
+                self.rose.validate_item(&edited)?;
+                let warning = match field {
+                    Field::Name(name) => self.warning(name),
+                    Field::SellIn(_) | Field::Quality(_) => String::new(),
+                };
+                let message = format!("{:>3}  {}{}", index, edited, warning);
+                self.rose.items[*index] = edited;
+                self.last_update[*index] = None;
+                Ok(message)
+            }
+            Op::Tick(days) => {
+                // Only the last day is explained, so the days before it just update.
+                for _ in 1..*days {
+                    self.rose.update_quality();
+                }
+                self.last_update = (0..self.rose.items.len())
+                    .map(|i| Explanation::of(&self.rose, i))
+                    .collect();
+                self.rose.update_quality();
+                Ok(self.table(|_| true))
+            }
+        }
+    }
+
+    // A `warning:` line for a near-miss name, or nothing.
+    fn warning(&self, name: &str) -> String {
+        if self.rose.is_custom_kind(name) {
+            return String::new();
+        }
+        lint::check(self.rose.profile(), name)
+            .map_or(String::new(), |n| format!("\nwarning: {}", n))
+    }
+
+    fn rebuild(&mut self) {
+        self.rose = (self.make)(copy(&self.initial));
+        self.last_update = vec![None; self.initial.len()];
+        for op in std::mem::take(&mut self.journal) {
+            // Every journaled change was valid when it was made, and replays the same way.
+            if self.perform(&op).is_ok() {
+                self.journal.push(op);
+            }
+        }
+    }
+}
+
+fn parse_field(s: &str) -> Result<Field, String> {
+    let usage = || format!("expected name=, sell_in= or quality=, got {:?}", s);
+    let (field, value) = s.split_once('=').ok_or_else(usage)?;
+    match field {
+        "name" if !value.is_empty() => Ok(Field::Name(value.to_string())),
+        "sell_in" => value.parse().map(Field::SellIn).map_err(|_| usage()),
+        "quality" => value.parse().map(Field::Quality).map_err(|_| usage()),
+        _ => Err(usage()),
+    }
+}
+
+fn describe(e: &Explanation, when: &str) -> String {
+    let mut lines = vec![format!(
+        "{} ({}, {}): {}, {} -> {}, {}",
+        e.name, e.kind, when, e.sell_in, e.quality, e.next_sell_in, e.next_quality
+    )];
+    lines.extend(e.reasons.iter().map(|r| format!("  - {}", r)));
+    lines.join("\n")
+}
+
+fn copy(items: &[Item]) -> Vec<Item> {
+    items
+        .iter()
+        .map(|it| Item::new(it.name.clone(), it.sell_in, it.quality))
+        .collect()
+}
+
+fn common_prefix(candidates: &[String]) -> String {
+    let Some(first) = candidates.first() else {
+        return String::new();
+    };
+    let mut len = first.len();
+    for c in &candidates[1..] {
+        len = first
+            .char_indices()
+            .zip(c.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8())
+            .min(len);
+    }
+    first[..len].to_string()
+}
+
+// Non-canonical, no-echo terminal input for the line editor; restored on drop. Signal keys
+// are turned off too (`-isig`), so Ctrl-C reaches the editor as a byte and ends the session
+// through `Drop` instead of killing the process with the terminal still in this mode.
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn enable() -> Option<RawMode> {
+        let saved = Command::new("stty")
+            .arg("-g")
+            .stdin(Stdio::inherit())
+            .output()
+            .ok()
+            .filter(|out| out.status.success())?;
+        let saved = String::from_utf8(saved.stdout).ok()?.trim().to_string();
+        Command::new("stty")
+            .args(["-icanon", "-echo", "-isig", "min", "1"])
+            .stdin(Stdio::inherit())
+            .status()
+            .ok()
+            .filter(|status| status.success())?;
+        Some(RawMode { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = Command::new("stty")
+            .arg(&self.saved)
+            .stdin(Stdio::inherit())
+            .status();
+    }
+}
@@ -0,0 +1,121 @@
+use crate::pattern;
+use std::borrow::Cow;
+
+// How much a perishable good loses per day. Every curve yields a non-negative step that is
+// then scaled by modifiers and applied with `dec_to_floor`, so `QUALITY_MIN` still holds.
+// After the sell-by date the same step is applied again in the expiry pass (twice as fast).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum DecayCurve {
+    // Flat `rate` per day (Normal items are `Linear { rate: 1 }`).
+    Linear {
+        rate: i32,
+    },
+    // First `(at_or_above, rate)` band whose bound `sell_in` reaches, else `otherwise`.
+    // Bands are checked in order, so list them from the highest bound down (`parse` sorts them).
+    Stepped {
+        bands: Vec<(i32, i32)>,
+        otherwise: i32,
+    },
+    // `percent` of the current quality, rounded up, and at least `min`.
+    Percent {
+        percent: i32,
+        min: i32,
+    },
+    // Explicit `(sell_in, rate)` entries, else `otherwise`.
+    Table {
+        rates: Vec<(i32, i32)>,
+        otherwise: i32,
+    },
+}
+
+impl DecayCurve {
+    // Today's loss for an item at `sell_in` (before the decrement) with `quality`.
+    #[must_use]
+    pub fn step(&self, sell_in: i32, quality: i32) -> i32 {
+        let step = match self {
+            DecayCurve::Linear { rate } => *rate,
+            DecayCurve::Stepped { bands, otherwise } => bands
+                .iter()
+                .find(|(at_or_above, _)| sell_in >= *at_or_above)
+                .map_or(*otherwise, |(_, rate)| *rate),
+            DecayCurve::Percent { percent, min } => {
+                // Ceiling division in i64 so large qualities or percentages cannot overflow.
+                let q = i64::from(quality.max(0));
+                let scaled = (q * i64::from(*percent) + 99).div_euclid(100);
+                i32::try_from(scaled).unwrap_or(i32::MAX).max(*min)
+            }
+            DecayCurve::Table { rates, otherwise } => rates
+                .iter()
+                .find(|(at, _)| *at == sell_in)
+                .map_or(*otherwise, |(_, rate)| *rate),
+        };
+        step.max(0)
+    }
+
+    // Parse `linear:N`, `percent:P[:MIN]`, `stepped:AT:RATE,...,*:RATE` or `table:SELL_IN:RATE,...,*:RATE`.
+    // The `*:RATE` entry is the fallback and defaults to 0 when omitted.
+    pub fn parse(s: &str) -> Option<DecayCurve> {
+        let (kind, args) = s.split_once(':')?;
+        let int = |n: &str| n.parse::<i32>().ok();
+        match kind {
+            "linear" => Some(DecayCurve::Linear { rate: int(args)? }),
+            "percent" => {
+                let (percent, min) = match args.split_once(':') {
+                    Some((p, m)) => (int(p)?, int(m)?),
+                    None => (int(args)?, 0),
+                };
+                Some(DecayCurve::Percent { percent, min })
+            }
+            "stepped" | "table" => {
+                let mut entries = Vec::new();
+                let mut otherwise = 0;
+                for entry in args.split(',') {
+                    match entry.split_once(':')? {
+                        ("*", rate) => otherwise = int(rate)?,
+                        (at, rate) => entries.push((int(at)?, int(rate)?)),
+                    }
+                }
+                Some(if kind == "stepped" {
+                    // Highest bound first, so `3:2,6:1` means the same as `6:1,3:2`.
+                    entries.sort_by_key(|&(at_or_above, _)| std::cmp::Reverse(at_or_above));
+                    DecayCurve::Stepped {
+                        bands: entries,
+                        otherwise,
+                    }
+                } else {
+                    DecayCurve::Table {
+                        rates: entries,
+                        otherwise,
+                    }
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+// Registry entry: base names matching `pattern` decay along `curve`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Perishable {
+    pub pattern: Cow<'static, str>,
+    pub curve: DecayCurve,
+}
+
+impl Perishable {
+    // Parse `PATTERN=CURVE`, see `DecayCurve::parse`.
+    pub fn parse(spec: &str) -> Option<Perishable> {
+        let (pattern, curve) = spec.rsplit_once('=')?;
+        if pattern.is_empty() {
+            return None;
+        }
+        Some(Perishable {
+            pattern: Cow::Owned(pattern.to_string()),
+            curve: DecayCurve::parse(curve)?,
+        })
+    }
+
+    #[must_use]
+    pub fn matches(&self, name: &str) -> bool {
+        pattern::matches(&self.pattern, name)
+    }
+}
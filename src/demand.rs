@@ -0,0 +1,238 @@
+use crate::appreciating::AfterExpiry;
+use crate::gilded_rose::GildedRose;
+use crate::pattern::Selector;
+use crate::sales::{Ledger, Pricing, SalePolicy};
+use crate::spec::{Kind, QUALITY_MIN};
+use crate::supply::Supply;
+
+// SplitMix64: small, fast and fully determined by its seed on every platform.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in `0..=max`. The modulo bias is negligible for the small ranges used here.
+    pub fn up_to(&mut self, max: u32) -> u32 {
+        let n = self.next_u64() % (u64::from(max) + 1);
+        u32::try_from(n).unwrap_or(max)
+    }
+}
+
+// Customers for items matching `selector` (a name glob or a kind, as for reorder rules): each
+// day brings a uniform number of them in `0..=2 * mean`, so `mean` per day on average. Each
+// buys one unit.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DemandRule {
+    pub selector: Selector,
+    pub mean: u32,
+}
+
+impl DemandRule {
+    // One customer per day on average for anything in stock.
+    pub fn anything() -> DemandRule {
+        DemandRule {
+            selector: Selector::Name("*".to_string()),
+            mean: 1,
+        }
+    }
+
+    // Parse `PATTERN=MEAN` or `kind:KIND=MEAN`.
+    pub fn parse(s: &str) -> Option<DemandRule> {
+        let (selector, mean) = s.rsplit_once('=')?;
+        Some(DemandRule {
+            selector: Selector::parse(selector)?,
+            mean: mean.parse().ok()?,
+        })
+    }
+}
+
+// Which of the matching, sellable items a customer picks. Ties go to the first in inventory.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Preference {
+    #[default]
+    HigherQuality,
+    LowerPrice,
+}
+
+impl Preference {
+    pub fn parse(s: &str) -> Option<Preference> {
+        match s {
+            "quality" => Some(Preference::HigherQuality),
+            "price" => Some(Preference::LowerPrice),
+            _ => None,
+        }
+    }
+}
+
+// One simulated run of `MonteCarlo::days` days.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct TrialOutcome {
+    pub revenue_cents: u64,
+    pub sold: u64,
+    // Items thrown away once expired at `QUALITY_MIN`, see `is_waste`.
+    pub waste: u64,
+    // Customers who found nothing to buy.
+    pub stock_outs: u64,
+}
+
+// Summary of one measure over all trials; percentiles use the nearest-rank method.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Distribution {
+    pub min: u64,
+    pub p10: u64,
+    pub median: u64,
+    pub p90: u64,
+    pub max: u64,
+    // Rounded down.
+    pub mean: u64,
+}
+
+impl Distribution {
+    pub fn of(mut values: Vec<u64>) -> Distribution {
+        if values.is_empty() {
+            return Distribution::default();
+        }
+        values.sort_unstable();
+        let n = values.len();
+        let rank = |p: usize| values[(p * n).div_ceil(100).clamp(1, n) - 1];
+        let sum: u128 = values.iter().map(|&v| u128::from(v)).sum();
+        Distribution {
+            min: values[0],
+            p10: rank(10),
+            median: rank(50),
+            p90: rank(90),
+            max: values[n - 1],
+            mean: u64::try_from(sum / n as u128).unwrap_or(u64::MAX),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct MonteCarloReport {
+    pub trials: Vec<TrialOutcome>,
+    pub revenue_cents: Distribution,
+    pub waste: Distribution,
+    pub stock_outs: Distribution,
+}
+
+// Seeded demand layered on the day loop. Trial `t` uses seed `seed + t`, so identical
+// settings give identical reports and any single trial can be replayed on its own.
+#[derive(Debug, Clone)]
+pub struct MonteCarlo {
+    pub seed: u64,
+    pub trials: u32,
+    pub days: u32,
+    pub demand: Vec<DemandRule>,
+    pub preference: Preference,
+    pub pricing: Pricing,
+    pub policy: SalePolicy,
+}
+
+impl MonteCarlo {
+    pub fn new(seed: u64, trials: u32, days: u32, demand: Vec<DemandRule>) -> MonteCarlo {
+        MonteCarlo {
+            seed,
+            trials,
+            days,
+            demand,
+            preference: Preference::default(),
+            pricing: Pricing::default(),
+            policy: SalePolicy::default(),
+        }
+    }
+
+    // `make` builds the starting inventory afresh for every trial; `supply` is cloned per trial.
+    pub fn run(&self, make: impl Fn() -> GildedRose, supply: &Supply) -> MonteCarloReport {
+        let trials: Vec<TrialOutcome> = (0..self.trials)
+            .map(|t| self.trial(u64::from(t), make(), supply.clone()))
+            .collect();
+        let measure =
+            |f: fn(&TrialOutcome) -> u64| Distribution::of(trials.iter().map(f).collect());
+        MonteCarloReport {
+            revenue_cents: measure(|t| t.revenue_cents),
+            waste: measure(|t| t.waste),
+            stock_outs: measure(|t| t.stock_outs),
+            trials,
+        }
+    }
+
+    // Each day: deliveries arrive, customers buy, items age, spoiled items are discarded.
+    pub fn trial(&self, trial: u64, mut rose: GildedRose, mut supply: Supply) -> TrialOutcome {
+        let mut rng = Rng::new(self.seed.wrapping_add(trial));
+        let mut ledger = Ledger::new(self.pricing, self.policy);
+        let mut outcome = TrialOutcome::default();
+        for _ in 0..self.days {
+            supply.start_day(&mut rose);
+            for rule in &self.demand {
+                let customers = rng.up_to(rule.mean.saturating_mul(2));
+                for _ in 0..customers {
+                    let sold = self
+                        .pick(&rose, &ledger, rule)
+                        .and_then(|index| ledger.sell(&mut rose, index).ok());
+                    match sold {
+                        Some(sale) => {
+                            outcome.revenue_cents =
+                                outcome.revenue_cents.saturating_add(sale.total_cents());
+                            outcome.sold += 1;
+                        }
+                        None => outcome.stock_outs += 1,
+                    }
+                }
+            }
+            rose.update_quality();
+            let before = rose.items.len();
+            let profile = rose.profile();
+            let waste: Vec<bool> = rose
+                .items
+                .iter()
+                .map(|it| is_waste(&profile.kind_of(&it.name), it.sell_in, it.quality))
+                .collect();
+            let mut waste = waste.into_iter();
+            rose.items.retain(|_| !waste.next().unwrap_or(false));
+            outcome.waste += rose.items.len().abs_diff(before) as u64;
+        }
+        outcome
+    }
+
+    fn pick(&self, rose: &GildedRose, ledger: &Ledger, rule: &DemandRule) -> Option<usize> {
+        let offers = rose
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, it)| rule.selector.matches(rose, &it.name))
+            .filter_map(|(i, it)| Some((i, it.quality, ledger.quote(rose.profile(), it).ok()?)));
+        // `min_by_key` keeps the first of equal keys, so ties go to the earlier item.
+        let best = match self.preference {
+            Preference::HigherQuality => offers.min_by_key(|&(_, q, _)| std::cmp::Reverse(q)),
+            Preference::LowerPrice => offers.min_by_key(|&(_, _, price)| price),
+        };
+        best.map(|(i, _, _)| i)
+    }
+}
+
+// Expired at `QUALITY_MIN` can never be sold again, unless it still gains quality.
+#[must_use]
+fn is_waste(kind: &Kind<'_>, sell_in: i32, quality: i32) -> bool {
+    let spent = sell_in < 0 && quality <= QUALITY_MIN;
+    match kind {
+        Kind::Legendary(_) => false,
+        Kind::Appreciating(a) => match a.after_expiry {
+            AfterExpiry::Continue { .. } | AfterExpiry::Plateau => false,
+            AfterExpiry::Spoil { .. } => spent,
+        },
+        Kind::BackstagePass | Kind::Perishable(_) | Kind::Normal => spent,
+    }
+}
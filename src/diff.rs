@@ -0,0 +1,240 @@
+use crate::gilded_rose::Item;
+use crate::json::Json;
+use std::fmt::{self, Display};
+
+// How items of the two inventories are paired up.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Matching {
+    // The `k`-th item named `n` before is the `k`-th item named `n` after, wherever it sits;
+    // for inventories that were stocked separately, like staging and prod.
+    #[default]
+    Name,
+    // Index `i` before is index `i` after if the name is unchanged; for one inventory a few
+    // ticks apart, where items with the same name must not swap places.
+    Position,
+}
+
+impl Matching {
+    // Parse `name` or `position`.
+    pub fn parse(s: &str) -> Option<Matching> {
+        match s {
+            "name" => Some(Matching::Name),
+            "position" => Some(Matching::Position),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl Format {
+    // Parse `text` or `json`.
+    pub fn parse(s: &str) -> Option<Format> {
+        match s {
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+// One item that is only on one side, with its index there.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Entry {
+    pub index: usize,
+    pub name: String,
+    pub sell_in: i32,
+    pub quality: i32,
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} {}, {}, {}",
+            self.index, self.name, self.sell_in, self.quality
+        )
+    }
+}
+
+// A matched pair whose values differ; `fields` holds `(field, before, after)` for each change.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Modified {
+    pub before: usize,
+    pub after: usize,
+    pub name: String,
+    pub fields: Vec<(&'static str, i32, i32)>,
+}
+
+impl Display for Modified {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.before == self.after {
+            write!(f, "#{} {}:", self.after, self.name)?;
+        } else {
+            write!(f, "#{} (was #{}) {}:", self.after, self.before, self.name)?;
+        }
+        for (i, (field, before, after)) in self.fields.iter().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            write!(f, "{} {} {} -> {}", sep, field, before, after)?;
+        }
+        Ok(())
+    }
+}
+
+// What changed from one inventory to another. `removed` is in `before` order, `added` and
+// `modified` in `after` order; matched items with equal values are left out.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Diff {
+    pub added: Vec<Entry>,
+    pub removed: Vec<Entry>,
+    pub modified: Vec<Modified>,
+}
+
+impl Diff {
+    pub fn between(before: &[Item], after: &[Item], matching: Matching) -> Diff {
+        let pairs = match matching {
+            Matching::Name => by_name(before, after),
+            Matching::Position => by_position(before, after),
+        };
+        let mut diff = Diff::default();
+        for (i, it) in before.iter().enumerate() {
+            if !pairs.iter().any(|&(b, _)| b == i) {
+                diff.removed.push(entry(i, it));
+            }
+        }
+        for (j, it) in after.iter().enumerate() {
+            let Some(&(i, _)) = pairs.iter().find(|&&(_, a)| a == j) else {
+                diff.added.push(entry(j, it));
+                continue;
+            };
+            let was = &before[i];
+            let fields: Vec<(&'static str, i32, i32)> = [
+                ("sell_in", was.sell_in, it.sell_in),
+                ("quality", was.quality, it.quality),
+            ]
+            .into_iter()
+            .filter(|(_, b, a)| b != a)
+            .collect();
+            if !fields.is_empty() {
+                diff.modified.push(Modified {
+                    before: i,
+                    after: j,
+                    name: it.name.clone(),
+                    fields,
+                });
+            }
+        }
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+    // `{"added": [...], "removed": [...], "modified": [...]}`; entries are
+    // `{"index", "name", "sell_in", "quality"}`, modifications
+    // `{"before", "after", "name", "changes": {FIELD: {"before", "after"}}}`.
+    pub fn to_json(&self) -> Json {
+        let entries = |entries: &[Entry]| {
+            Json::Array(
+                entries
+                    .iter()
+                    .map(|e| {
+                        Json::object([
+                            ("index", e.index.into()),
+                            ("name", e.name.as_str().into()),
+                            ("sell_in", e.sell_in.into()),
+                            ("quality", e.quality.into()),
+                        ])
+                    })
+                    .collect(),
+            )
+        };
+        let modified = self
+            .modified
+            .iter()
+            .map(|m| {
+                let changes = m.fields.iter().map(|&(field, before, after)| {
+                    (
+                        field,
+                        Json::object([("before", before.into()), ("after", after.into())]),
+                    )
+                });
+                Json::object([
+                    ("before", m.before.into()),
+                    ("after", m.after.into()),
+                    ("name", m.name.as_str().into()),
+                    ("changes", Json::object(changes)),
+                ])
+            })
+            .collect();
+        Json::object([
+            ("added", entries(&self.added)),
+            ("removed", entries(&self.removed)),
+            ("modified", Json::Array(modified)),
+        ])
+    }
+}
+
+impl Display for Diff {
+    // A summary line, then `- ` removed, `+ ` added and `~ ` modified items, one per line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no changes");
+        }
+        write!(
+            f,
+            "{} added, {} removed, {} modified",
+            self.added.len(),
+            self.removed.len(),
+            self.modified.len()
+        )?;
+        for e in &self.removed {
+            write!(f, "\n- {}", e)?;
+        }
+        for e in &self.added {
+            write!(f, "\n+ {}", e)?;
+        }
+        for m in &self.modified {
+            write!(f, "\n~ {}", m)?;
+        }
+        Ok(())
+    }
+}
+
+fn entry(index: usize, it: &Item) -> Entry {
+    Entry {
+        index,
+        name: it.name.clone(),
+        sell_in: it.sell_in,
+        quality: it.quality,
+    }
+}
+
+// `(before index, after index)` for every matched pair.
+fn by_name(before: &[Item], after: &[Item]) -> Vec<(usize, usize)> {
+    let mut taken = vec![false; after.len()];
+    let mut pairs = Vec::new();
+    for (i, it) in before.iter().enumerate() {
+        let found = (0..after.len()).find(|&j| !taken[j] && after[j].name == it.name);
+        if let Some(j) = found {
+            taken[j] = true;
+            pairs.push((i, j));
+        }
+    }
+    pairs
+}
+
+fn by_position(before: &[Item], after: &[Item]) -> Vec<(usize, usize)> {
+    before
+        .iter()
+        .zip(after)
+        .enumerate()
+        .filter(|(_, (b, a))| b.name == a.name)
+        .map(|(i, _)| (i, i))
+        .collect()
+}
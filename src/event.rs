@@ -0,0 +1,107 @@
+use crate::profile::Profile;
+use crate::spec::PREFIX_BACKSTAGE;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+
+// Separators accepted between "Backstage passes" and the event name, longest first.
+const EVENT_SEPARATORS: [&str; 6] = [" to the ", " to an ", " to a ", " to ", " for ", " - "];
+
+// Extract the event from a Backstage pass base name:
+// "Backstage passes to a TAFKAL80ETC concert" -> "TAFKAL80ETC concert", "Backstage passes - Hall" -> "Hall".
+#[must_use]
+pub fn backstage_event(base_name: &str) -> Option<&str> {
+    let rest = base_name.strip_prefix(PREFIX_BACKSTAGE)?;
+    let event = EVENT_SEPARATORS
+        .iter()
+        .find_map(|sep| rest.strip_prefix(sep))?;
+    (!event.is_empty()).then_some(event)
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum EventStatus {
+    // Days until the concert, with the same meaning as a pass's `sell_in`.
+    Scheduled { days_left: i32 },
+    // All passes are worthless from the cancellation on.
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum EventError {
+    Unknown(String),
+    Cancelled(String),
+}
+
+impl Display for EventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventError::Unknown(name) => write!(f, "unknown event: {}", name),
+            EventError::Cancelled(name) => write!(f, "event already cancelled: {}", name),
+        }
+    }
+}
+
+// A pass whose quality was zeroed by a cancellation; `quality` is the refund basis.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Refund {
+    pub index: usize,
+    pub name: String,
+    pub quality: i32,
+}
+
+// The authoritative date of each registered event. Passes for an unregistered event keep
+// using their own `sell_in`.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct EventRegistry {
+    events: BTreeMap<String, EventStatus>,
+}
+
+impl EventRegistry {
+    #[must_use]
+    pub fn status(&self, event: &str) -> Option<EventStatus> {
+        self.events.get(event).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, EventStatus)> {
+        self.events
+            .iter()
+            .map(|(name, status)| (name.as_str(), *status))
+    }
+
+    // Register (or re-date) an event; this also revives a cancelled one.
+    pub fn schedule(&mut self, event: impl Into<String>, days_left: i32) {
+        self.events
+            .insert(event.into(), EventStatus::Scheduled { days_left });
+    }
+
+    // Move the concert `days` later; returns the new `days_left`.
+    pub fn postpone(&mut self, event: &str, days: u32) -> Result<i32, EventError> {
+        match self.events.get_mut(event) {
+            None => Err(EventError::Unknown(event.to_string())),
+            Some(EventStatus::Cancelled) => Err(EventError::Cancelled(event.to_string())),
+            Some(EventStatus::Scheduled { days_left }) => {
+                *days_left = days_left.saturating_add_unsigned(days);
+                Ok(*days_left)
+            }
+        }
+    }
+
+    pub fn cancel(&mut self, event: &str) -> Result<(), EventError> {
+        match self.events.get_mut(event) {
+            None => Err(EventError::Unknown(event.to_string())),
+            Some(EventStatus::Cancelled) => Err(EventError::Cancelled(event.to_string())),
+            Some(status) => {
+                *status = EventStatus::Cancelled;
+                Ok(())
+            }
+        }
+    }
+
+    // One day passes for every scheduled event, like `sell_in` does for items.
+    pub fn tick(&mut self, profile: &Profile) {
+        for status in self.events.values_mut() {
+            if let EventStatus::Scheduled { days_left } = status {
+                *days_left = profile.next_sell_in(*days_left);
+            }
+        }
+    }
+}
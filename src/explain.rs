@@ -0,0 +1,144 @@
+use crate::appreciating::AfterExpiry;
+use crate::event::{EventStatus, backstage_event};
+use crate::gilded_rose::GildedRose;
+use crate::json::Json;
+use crate::spec::{Kind, QUALITY_MAX, QUALITY_MIN};
+
+// Why an item will change the way it does at the next `update_quality`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Explanation {
+    pub index: usize,
+    pub name: String,
+    // The name without modifier prefixes, as the classifier saw it.
+    pub base_name: String,
+    // `Kind::label`, or "custom" for an `ItemBehavior` kind.
+    pub kind: &'static str,
+    pub sell_in: i32,
+    pub quality: i32,
+    pub next_sell_in: i32,
+    pub next_quality: i32,
+    // One sentence per rule that applies, most specific last.
+    pub reasons: Vec<String>,
+}
+
+impl Explanation {
+    // Explain `rose.items[index]`; `None` if there is no such item.
+    pub fn of(rose: &GildedRose, index: usize) -> Option<Explanation> {
+        let it = rose.items.get(index)?;
+        let next = rose.forecast(index, 1)?.pop()?;
+        let profile = rose.profile();
+        let (mods, base_name) = profile.split_modifiers(&it.name);
+        let mut reasons = Vec::new();
+        let kind = if rose.is_custom_kind(&it.name) {
+            reasons.push("custom kind: rules come from a registered behavior".to_string());
+            "custom"
+        } else {
+            if let Some(alias) = profile.alias(base_name) {
+                reasons.push(match &alias.language {
+                    Some(lang) => format!("alias ({}) of {}", lang, alias.target),
+                    None => format!("alias of {}", alias.target),
+                });
+            }
+            let kind = profile.classify(base_name);
+            match &kind {
+                Kind::Legendary(q) => {
+                    reasons.push(format!("legendary: quality stays at {}, never sold", q))
+                }
+                Kind::Appreciating(a) => {
+                    // Only `Continue` keeps the daily gain from the sell-by date on.
+                    let continues = matches!(a.after_expiry, AfterExpiry::Continue { .. });
+                    reasons.push(if continues || it.sell_in > 0 {
+                        format!("appreciating: gains {} per day", mods.appreciate(a.rate))
+                    } else {
+                        "appreciating: stopped gaining at the sell-by date".into()
+                    });
+                    if next.sell_in.is_negative() {
+                        reasons.push(match a.after_expiry {
+                            AfterExpiry::Continue { extra } => format!(
+                                "past the sell-by date: gains {} more",
+                                mods.appreciate(extra)
+                            ),
+                            AfterExpiry::Plateau => "past the sell-by date: quality holds".into(),
+                            AfterExpiry::Spoil { rate } => format!(
+                                "past the sell-by date: loses {} per day",
+                                mods.degrade(rate)
+                            ),
+                        });
+                    }
+                }
+                Kind::BackstagePass => {
+                    let status = backstage_event(base_name)
+                        .and_then(|e| rose.events().status(e).map(|status| (e, status)));
+                    match status {
+                        Some((event, EventStatus::Scheduled { days_left })) => reasons.push(
+                            format!("follows event {:?}, {} days left", event, days_left),
+                        ),
+                        Some((event, EventStatus::Cancelled)) => reasons.push(format!(
+                            "event {:?} was cancelled: quality drops to 0",
+                            event
+                        )),
+                        None => (),
+                    }
+                    // An event moves `sell_in` before the update, so go by the new value.
+                    let days_left = next.sell_in.saturating_add(1);
+                    let cancelled = matches!(status, Some((_, EventStatus::Cancelled)));
+                    if !cancelled && next.sell_in.is_negative() {
+                        reasons
+                            .push("backstage: the concert has passed, quality drops to 0".into());
+                    } else if !cancelled {
+                        reasons.push(format!(
+                            "backstage: gains {} with {} days left",
+                            mods.appreciate(profile.backstage_increment(days_left)),
+                            days_left
+                        ));
+                    }
+                }
+                Kind::Perishable(curve) => reasons.push(format!(
+                    "perishable: loses {} today",
+                    mods.degrade(curve.step(it.sell_in, it.quality))
+                )),
+                Kind::Normal => reasons.push(format!("normal: loses {} per day", mods.degrade(1))),
+            }
+            let expires = matches!(kind, Kind::Normal | Kind::Perishable(_));
+            if expires && next.sell_in.is_negative() {
+                reasons.push("past the sell-by date: degrades twice as fast".into());
+            }
+            kind.label()
+        };
+        if mods.conjured > 0 {
+            reasons.push(format!("conjured x{}", mods.conjured));
+        }
+        if kind != "legendary" && next.quality != it.quality {
+            if next.quality == QUALITY_MAX {
+                reasons.push(format!("quality reaches the cap of {}", QUALITY_MAX));
+            } else if next.quality == QUALITY_MIN {
+                reasons.push(format!("quality reaches the floor of {}", QUALITY_MIN));
+            }
+        }
+        Some(Explanation {
+            index,
+            name: it.name.clone(),
+            base_name: base_name.to_string(),
+            kind,
+            sell_in: it.sell_in,
+            quality: it.quality,
+            next_sell_in: next.sell_in,
+            next_quality: next.quality,
+            reasons,
+        })
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::object([
+            ("index", self.index.into()),
+            ("name", self.name.as_str().into()),
+            ("base_name", self.base_name.as_str().into()),
+            ("kind", self.kind.into()),
+            ("sell_in", self.sell_in.into()),
+            ("quality", self.quality.into()),
+            ("next_sell_in", self.next_sell_in.into()),
+            ("next_quality", self.next_quality.into()),
+            ("reasons", self.reasons.clone().into()),
+        ])
+    }
+}
@@ -0,0 +1,567 @@
+use crate::behavior::{ItemBehavior, ItemContext};
+use crate::pattern;
+use std::fmt::{self, Display};
+
+// A tiny expression language for quality deltas, e.g. `if sell_in < 3 then -3 else -1`.
+//
+// Grammar, loosest binding first:
+//   expr  := "if" expr "then" expr "else" expr | or
+//   or    := and ("||" and)*
+//   and   := cmp ("&&" cmp)*
+//   cmp   := sum (("<" | "<=" | ">" | ">=" | "==" | "!=") sum)?
+//   sum   := term (("+" | "-") term)*
+//   term  := unary (("*" | "/" | "%") unary)*
+//   unary := ("-" | "!") unary | atom
+//   atom  := INTEGER | VARIABLE | ("min" | "max") "(" expr "," expr ")" | "(" expr ")"
+//
+// Values are `i64`; comparisons and `!`, `&&`, `||` yield 1 or 0 and treat non-zero as true.
+// There are no loops, calls into the host or assignments, nesting is capped at `MAX_DEPTH`
+// and length at `MAX_TOKENS` (which also bounds evaluation's recursion), and arithmetic is
+// checked, so evaluation always terminates without panicking.
+
+const MAX_DEPTH: usize = 64;
+const MAX_TOKENS: usize = 1024;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Var {
+    SellIn,
+    Quality,
+    // Number of "Conjured " prefixes on the name.
+    Conjured,
+    // `GildedRose::day()` before the update.
+    Day,
+}
+
+impl Var {
+    fn parse(name: &str) -> Option<Var> {
+        match name {
+            "sell_in" => Some(Var::SellIn),
+            "quality" => Some(Var::Quality),
+            "conjured" => Some(Var::Conjured),
+            "day" => Some(Var::Day),
+            _ => None,
+        }
+    }
+}
+
+// The values an expression can read.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Env {
+    pub sell_in: i64,
+    pub quality: i64,
+    pub conjured: i64,
+    pub day: i64,
+}
+
+impl Env {
+    fn get(&self, var: Var) -> i64 {
+        match var {
+            Var::SellIn => self.sell_in,
+            Var::Quality => self.quality,
+            Var::Conjured => self.conjured,
+            Var::Day => self.day,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+    Min,
+    Max,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum UnOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Expr {
+    Num(i64),
+    Var(Var),
+    Unary(UnOp, Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    If(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ParseError {
+    UnexpectedChar { offset: usize, ch: char },
+    // `found` is the offending token's text.
+    UnexpectedToken { offset: usize, found: String },
+    UnexpectedEnd,
+    // A rule without the `=` between its pattern and its expressions.
+    MissingAssignment,
+    UnknownVariable { offset: usize, name: String },
+    NumberTooLarge { offset: usize },
+    TooDeep,
+    TooLong,
+}
+
+impl ParseError {
+    // The same error with its offset moved `by` bytes, for an expression embedded in a longer
+    // string.
+    #[must_use]
+    fn shifted(self, by: usize) -> ParseError {
+        match self {
+            ParseError::UnexpectedChar { offset, ch } => ParseError::UnexpectedChar {
+                offset: offset + by,
+                ch,
+            },
+            ParseError::UnexpectedToken { offset, found } => ParseError::UnexpectedToken {
+                offset: offset + by,
+                found,
+            },
+            ParseError::UnknownVariable { offset, name } => ParseError::UnknownVariable {
+                offset: offset + by,
+                name,
+            },
+            ParseError::NumberTooLarge { offset } => ParseError::NumberTooLarge {
+                offset: offset + by,
+            },
+            ParseError::UnexpectedEnd
+            | ParseError::MissingAssignment
+            | ParseError::TooDeep
+            | ParseError::TooLong => self,
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedChar { offset, ch } => {
+                write!(f, "unexpected character {:?} at {}", ch, offset)
+            }
+            ParseError::UnexpectedToken { offset, found } => {
+                write!(f, "unexpected {:?} at {}", found, offset)
+            }
+            ParseError::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            ParseError::MissingAssignment => write!(f, "expected PATTERN=DAILY[;EXPIRY]"),
+            ParseError::UnknownVariable { offset, name } => {
+                write!(f, "unknown variable {:?} at {}", name, offset)
+            }
+            ParseError::NumberTooLarge { offset } => write!(f, "number too large at {}", offset),
+            ParseError::TooDeep => write!(f, "expression nested deeper than {}", MAX_DEPTH),
+            ParseError::TooLong => write!(f, "expression longer than {} tokens", MAX_TOKENS),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum EvalError {
+    Overflow,
+    DivisionByZero,
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::Overflow => write!(f, "arithmetic overflow"),
+            EvalError::DivisionByZero => write!(f, "division by zero"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Num(i64),
+    Ident(String),
+    // Operators and punctuation, e.g. "<=" or "(".
+    Sym(&'static str),
+}
+
+// Longest first, so "<=" wins over "<".
+const SYMBOLS: [&str; 17] = [
+    "<=", ">=", "==", "!=", "&&", "||", "<", ">", "+", "-", "*", "/", "%", "!", "(", ")", ",",
+];
+
+fn tokenize(src: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let mut tokens = Vec::new();
+    let bytes = src.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        if c.is_ascii_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+            let n = src[start..i]
+                .parse()
+                .map_err(|_| ParseError::NumberTooLarge { offset: start })?;
+            tokens.push((start, Token::Num(n)));
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            let start = i;
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            tokens.push((start, Token::Ident(src[start..i].to_string())));
+        } else if let Some(sym) = SYMBOLS.iter().find(|s| src[i..].starts_with(**s)) {
+            tokens.push((i, Token::Sym(sym)));
+            i += sym.len();
+        } else {
+            let ch = src[i..].chars().next().unwrap_or('\0');
+            return Err(ParseError::UnexpectedChar { offset: i, ch });
+        }
+        if tokens.len() > MAX_TOKENS {
+            return Err(ParseError::TooLong);
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn next(&mut self) -> Result<(usize, Token), ParseError> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or(ParseError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat_sym(&mut self, sym: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Sym(s)) if *s == sym) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn eat_keyword(&mut self, word: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(w)) if w == word) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect_sym(&mut self, sym: &str) -> Result<(), ParseError> {
+        if self.eat_sym(sym) {
+            return Ok(());
+        }
+        Err(self.unexpected())
+    }
+
+    fn expect_keyword(&mut self, word: &str) -> Result<(), ParseError> {
+        if self.eat_keyword(word) {
+            return Ok(());
+        }
+        Err(self.unexpected())
+    }
+
+    fn unexpected(&self) -> ParseError {
+        match self.tokens.get(self.pos) {
+            None => ParseError::UnexpectedEnd,
+            Some((offset, token)) => ParseError::UnexpectedToken {
+                offset: *offset,
+                found: match token {
+                    Token::Num(n) => n.to_string(),
+                    Token::Ident(name) => name.clone(),
+                    Token::Sym(sym) => sym.to_string(),
+                },
+            },
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(ParseError::TooDeep);
+        }
+        let e = if self.eat_keyword("if") {
+            let cond = self.expr()?;
+            self.expect_keyword("then")?;
+            let then = self.expr()?;
+            self.expect_keyword("else")?;
+            let otherwise = self.expr()?;
+            Expr::If(Box::new(cond), Box::new(then), Box::new(otherwise))
+        } else {
+            self.or()?
+        };
+        self.depth -= 1;
+        Ok(e)
+    }
+
+    fn or(&mut self) -> Result<Expr, ParseError> {
+        let mut e = self.and()?;
+        while self.eat_sym("||") {
+            e = Expr::Binary(BinOp::Or, Box::new(e), Box::new(self.and()?));
+        }
+        Ok(e)
+    }
+
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        let mut e = self.cmp()?;
+        while self.eat_sym("&&") {
+            e = Expr::Binary(BinOp::And, Box::new(e), Box::new(self.cmp()?));
+        }
+        Ok(e)
+    }
+
+    fn cmp(&mut self) -> Result<Expr, ParseError> {
+        let e = self.sum()?;
+        let op = match self.peek() {
+            Some(Token::Sym("<")) => BinOp::Lt,
+            Some(Token::Sym("<=")) => BinOp::Le,
+            Some(Token::Sym(">")) => BinOp::Gt,
+            Some(Token::Sym(">=")) => BinOp::Ge,
+            Some(Token::Sym("==")) => BinOp::Eq,
+            Some(Token::Sym("!=")) => BinOp::Ne,
+            _ => return Ok(e),
+        };
+        self.pos += 1;
+        Ok(Expr::Binary(op, Box::new(e), Box::new(self.sum()?)))
+    }
+
+    fn sum(&mut self) -> Result<Expr, ParseError> {
+        let mut e = self.term()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Sym("+")) => BinOp::Add,
+                Some(Token::Sym("-")) => BinOp::Sub,
+                _ => return Ok(e),
+            };
+            self.pos += 1;
+            e = Expr::Binary(op, Box::new(e), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, ParseError> {
+        let mut e = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Sym("*")) => BinOp::Mul,
+                Some(Token::Sym("/")) => BinOp::Div,
+                Some(Token::Sym("%")) => BinOp::Rem,
+                _ => return Ok(e),
+            };
+            self.pos += 1;
+            e = Expr::Binary(op, Box::new(e), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        let op = if self.eat_sym("-") {
+            UnOp::Neg
+        } else if self.eat_sym("!") {
+            UnOp::Not
+        } else {
+            return self.atom();
+        };
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(ParseError::TooDeep);
+        }
+        let e = Expr::Unary(op, Box::new(self.unary()?));
+        self.depth -= 1;
+        Ok(e)
+    }
+
+    fn atom(&mut self) -> Result<Expr, ParseError> {
+        let (offset, token) = self.next()?;
+        match token {
+            Token::Num(n) => Ok(Expr::Num(n)),
+            Token::Ident(name) if name == "min" || name == "max" => {
+                let op = if name == "min" {
+                    BinOp::Min
+                } else {
+                    BinOp::Max
+                };
+                self.expect_sym("(")?;
+                let a = self.expr()?;
+                self.expect_sym(",")?;
+                let b = self.expr()?;
+                self.expect_sym(")")?;
+                Ok(Expr::Binary(op, Box::new(a), Box::new(b)))
+            }
+            Token::Ident(name) => match Var::parse(&name) {
+                Some(var) => Ok(Expr::Var(var)),
+                None => Err(ParseError::UnknownVariable { offset, name }),
+            },
+            Token::Sym("(") => {
+                let e = self.expr()?;
+                self.expect_sym(")")?;
+                Ok(e)
+            }
+            Token::Sym(_) => {
+                self.pos -= 1;
+                Err(self.unexpected())
+            }
+        }
+    }
+}
+
+impl Expr {
+    pub fn parse(src: &str) -> Result<Expr, ParseError> {
+        let mut parser = Parser {
+            tokens: tokenize(src)?,
+            pos: 0,
+            depth: 0,
+        };
+        let e = parser.expr()?;
+        if parser.pos < parser.tokens.len() {
+            return Err(parser.unexpected());
+        }
+        Ok(e)
+    }
+
+    pub fn eval(&self, env: &Env) -> Result<i64, EvalError> {
+        let flag = |b: bool| i64::from(b);
+        match self {
+            Expr::Num(n) => Ok(*n),
+            Expr::Var(var) => Ok(env.get(*var)),
+            Expr::Unary(UnOp::Neg, e) => e.eval(env)?.checked_neg().ok_or(EvalError::Overflow),
+            Expr::Unary(UnOp::Not, e) => Ok(flag(e.eval(env)? == 0)),
+            Expr::If(cond, then, otherwise) => {
+                if cond.eval(env)? != 0 {
+                    then.eval(env)
+                } else {
+                    otherwise.eval(env)
+                }
+            }
+            // Short-circuit, so `sell_in != 0 && 10 / sell_in > 2` is safe.
+            Expr::Binary(BinOp::And, a, b) => Ok(flag(a.eval(env)? != 0 && b.eval(env)? != 0)),
+            Expr::Binary(BinOp::Or, a, b) => Ok(flag(a.eval(env)? != 0 || b.eval(env)? != 0)),
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.eval(env)?, b.eval(env)?);
+                match op {
+                    BinOp::Add => a.checked_add(b).ok_or(EvalError::Overflow),
+                    BinOp::Sub => a.checked_sub(b).ok_or(EvalError::Overflow),
+                    BinOp::Mul => a.checked_mul(b).ok_or(EvalError::Overflow),
+                    BinOp::Div | BinOp::Rem if b == 0 => Err(EvalError::DivisionByZero),
+                    // `i64::MIN / -1` is the only remaining failure.
+                    BinOp::Div => a.checked_div(b).ok_or(EvalError::Overflow),
+                    BinOp::Rem => a.checked_rem(b).ok_or(EvalError::Overflow),
+                    BinOp::Lt => Ok(flag(a < b)),
+                    BinOp::Le => Ok(flag(a <= b)),
+                    BinOp::Gt => Ok(flag(a > b)),
+                    BinOp::Ge => Ok(flag(a >= b)),
+                    BinOp::Eq => Ok(flag(a == b)),
+                    BinOp::Ne => Ok(flag(a != b)),
+                    BinOp::Min => Ok(a.min(b)),
+                    BinOp::Max => Ok(a.max(b)),
+                    BinOp::And | BinOp::Or => unreachable!("short-circuited above"),
+                }
+            }
+        }
+    }
+}
+
+// Base names matching `pattern` change quality by `daily` each day and additionally by
+// `expiry` once past the sell-by date. Both are deltas: `-3` loses 3.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ExprRule {
+    pub pattern: String,
+    pub daily: Expr,
+    pub expiry: Option<Expr>,
+}
+
+impl ExprRule {
+    // Parse `PATTERN=DAILY[;EXPIRY]`, e.g. `Mana Cake=if sell_in < 3 then -3 else -1;-2`.
+    // Error offsets are into `spec`.
+    pub fn parse(spec: &str) -> Result<ExprRule, ParseError> {
+        let (pattern, exprs) = spec.split_once('=').ok_or(ParseError::MissingAssignment)?;
+        let start = pattern.len() + 1;
+        let (daily, expiry) = match exprs.split_once(';') {
+            Some((daily, expiry)) => {
+                let expiry = Expr::parse(expiry).map_err(|e| e.shifted(start + daily.len() + 1))?;
+                (daily, Some(expiry))
+            }
+            None => (exprs, None),
+        };
+        Ok(ExprRule {
+            pattern: pattern.to_string(),
+            daily: Expr::parse(daily).map_err(|e| e.shifted(start))?,
+            expiry,
+        })
+    }
+}
+
+// Compiled expression rules, used as one custom kind (`GildedRose::add_behavior`): the first
+// rule matching a base name owns the item. Expressions are parsed once, when the set is
+// built. Modifiers are not applied to the deltas; rules read `conjured` instead. A delta that
+// fails to evaluate (overflow, division by zero) leaves the quality unchanged for that step.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct RuleSet {
+    rules: Vec<ExprRule>,
+}
+
+impl RuleSet {
+    pub fn new(rules: Vec<ExprRule>) -> RuleSet {
+        RuleSet { rules }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    fn rule(&self, base_name: &str) -> Option<&ExprRule> {
+        self.rules
+            .iter()
+            .find(|r| pattern::matches(&r.pattern, base_name))
+    }
+
+    fn apply(expr: &Expr, ctx: &ItemContext<'_>, sell_in: i32, quality: i32) -> i32 {
+        let env = Env {
+            sell_in: i64::from(sell_in),
+            quality: i64::from(quality),
+            conjured: i64::from(ctx.mods.conjured),
+            day: i64::from(ctx.day),
+        };
+        let delta = expr
+            .eval(&env)
+            .ok()
+            .and_then(|d| i32::try_from(d).ok())
+            .unwrap_or(0);
+        quality.saturating_add(delta)
+    }
+}
+
+impl ItemBehavior for RuleSet {
+    fn classify(&self, base_name: &str) -> bool {
+        self.rule(base_name).is_some()
+    }
+
+    fn daily(&self, ctx: &ItemContext<'_>, sell_in: i32, quality: i32) -> i32 {
+        match self.rule(ctx.base_name) {
+            Some(rule) => Self::apply(&rule.daily, ctx, sell_in, quality),
+            None => quality,
+        }
+    }
+
+    fn expiry(&self, ctx: &ItemContext<'_>, sell_in: i32, quality: i32) -> i32 {
+        match self.rule(ctx.base_name).and_then(|r| r.expiry.as_ref()) {
+            Some(expiry) => Self::apply(expiry, ctx, sell_in, quality),
+            None => quality,
+        }
+    }
+}
@@ -1,5 +1,12 @@
-use crate::spec::{Kind, assert_preconditions, dec_to_floor, inc_to_cap, split_conjured};
+use crate::appreciating::AfterExpiry;
+use crate::behavior::{ItemBehavior, ItemContext};
+use crate::event::{EventError, EventRegistry, EventStatus, Refund, backstage_event};
+use crate::lint::{self, NearMiss};
+use crate::observer::{Bound, Observer};
+use crate::profile::{LegendaryPolicy, Profile};
+use crate::spec::{Kind, assert_preconditions, check_preconditions, dec_to_floor, inc_to_cap};
 use std::fmt::{self, Display};
+use std::ops::RangeInclusive;
 
 //  Requirements for the Gilded Rose system:
 //    1. Item class definition and its method remain unchanged.
@@ -28,6 +35,13 @@ impl Display for Item {
 
 pub struct GildedRose {
     pub items: Vec<Item>,
+    profile: Profile,
+    events: EventRegistry,
+    // Number of `update_quality` calls so far; day 0 is the initial stock.
+    day: u32,
+    observers: Vec<Box<dyn Observer>>,
+    // Custom kinds, asked in order before the built-in ones.
+    behaviors: Vec<Box<dyn ItemBehavior>>,
 }
 
 //  Requirements for the Gilded Rose system:
@@ -35,63 +49,408 @@ pub struct GildedRose {
 //
 impl GildedRose {
     pub fn new(items: Vec<Item>) -> GildedRose {
-        GildedRose { items }
+        Self::with_profile(items, Profile::default())
     }
 
-    pub fn update_quality(&mut self) {
+    pub fn with_profile(items: Vec<Item>, profile: Profile) -> GildedRose {
+        GildedRose {
+            items,
+            profile,
+            events: EventRegistry::default(),
+            day: 0,
+            observers: Vec::new(),
+            behaviors: Vec::new(),
+        }
+    }
+
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+
+    pub fn events(&self) -> &EventRegistry {
+        &self.events
+    }
+
+    // Observers are called in registration order during every `update_quality`.
+    pub fn add_observer(&mut self, observer: Box<dyn Observer>) {
+        self.observers.push(observer);
+    }
+
+    // Add a custom kind to the end of the classifier chain, see `ItemBehavior`.
+    pub fn add_behavior(&mut self, behavior: Box<dyn ItemBehavior>) {
+        self.behaviors.push(behavior);
+    }
+
+    // Items that break their kind's preconditions, with the reason; the runtime counterpart
+    // of the debug assertions in `update_quality`.
+    pub fn validate(&self) -> Vec<(usize, String)> {
+        self.items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, it)| self.validate_item(it).err().map(|e| (index, e)))
+            .collect()
+    }
+
+    // Items whose names are near misses of a special name or prefix, with a suggestion;
+    // names owned by a custom kind are left alone. Unlike `validate`, these are warnings.
+    pub fn lint(&self) -> Vec<(usize, NearMiss)> {
+        self.items
+            .iter()
+            .enumerate()
+            .filter(|(_, it)| !self.is_custom_kind(&it.name))
+            .filter_map(|(index, it)| lint::check(&self.profile, &it.name).map(|n| (index, n)))
+            .collect()
+    }
+
+    // Check an item against the kind it would have here, e.g. before adding it.
+    pub fn validate_item(&self, it: &Item) -> Result<(), String> {
+        let (mods, base_name) = self.profile.split_modifiers(&it.name);
+        match find_behavior(&self.behaviors, base_name) {
+            Some(b) => {
+                let ctx = ItemContext {
+                    day: self.day,
+                    base_name,
+                    mods,
+                    profile: &self.profile,
+                };
+                b.validate(&ctx, it.sell_in, it.quality)
+            }
+            None => check_preconditions(&self.profile.classify(base_name), it.quality),
+        }
+    }
+
+    // Whether a registered `ItemBehavior` owns items with this name.
+    pub fn is_custom_kind(&self, name: &str) -> bool {
+        let (_, base_name) = self.profile.split_modifiers(name);
+        find_behavior(&self.behaviors, base_name).is_some()
+    }
+
+    // `Kind::label` of the kind this name has here, or "custom" for an `ItemBehavior` kind.
+    pub fn kind_label(&self, name: &str) -> &'static str {
+        if self.is_custom_kind(name) {
+            "custom"
+        } else {
+            self.profile.kind_of(name).label()
+        }
+    }
+
+    // Register an event date; every pass for it takes that date as its `sell_in` right away.
+    pub fn schedule_event(&mut self, event: &str, days_left: i32) {
+        self.events.schedule(event, days_left);
+        self.set_event_sell_in(event, days_left);
+    }
+
+    // Move an event `days` later; all of its passes move together.
+    pub fn postpone_event(&mut self, event: &str, days: u32) -> Result<(), EventError> {
+        let days_left = self.events.postpone(event, days)?;
+        self.set_event_sell_in(event, days_left);
+        Ok(())
+    }
+
+    // Cancel an event: its passes drop to 0 and the qualities they had are returned as refunds.
+    pub fn cancel_event(&mut self, event: &str) -> Result<Vec<Refund>, EventError> {
+        self.events.cancel(event)?;
+        let mut refunds = Vec::new();
+        for (index, it) in self.items.iter_mut().enumerate() {
+            if pass_event(&self.profile, &it.name) == Some(event) {
+                refunds.push(Refund {
+                    index,
+                    name: it.name.clone(),
+                    quality: it.quality,
+                });
+                it.quality = 0;
+            }
+        }
+        Ok(refunds)
+    }
+
+    fn set_event_sell_in(&mut self, event: &str, days_left: i32) {
         for it in &mut self.items {
-            Self::update_one_item(it);
+            if pass_event(&self.profile, &it.name) == Some(event) {
+                it.sell_in = days_left;
+            }
+        }
+    }
+
+    // Age a copy of `items[index]` for `days` days under the same profile and events, without
+    // touching the inventory. Entry `d` is the item as it will be `d` days from now.
+    pub fn forecast(&self, index: usize, days: u32) -> Option<Vec<Item>> {
+        let it = self.items.get(index)?;
+        let mut copy = Item::new(it.name.clone(), it.sell_in, it.quality);
+        let mut events = self.events.clone();
+        let mut states = Vec::new();
+        for d in 0..days {
+            states.push(Item::new(copy.name.clone(), copy.sell_in, copy.quality));
+            let day = self.day.saturating_add(d);
+            Self::update_one_item(
+                &self.profile,
+                &self.behaviors,
+                &events,
+                day,
+                &mut copy,
+                &mut |_| {},
+            );
+            events.tick(&self.profile);
         }
+        states.push(copy);
+        Some(states)
     }
 
-    fn update_one_item(it: &mut Item) {
+    pub fn update_quality(&mut self) {
+        if self.observers.is_empty() {
+            // The no-op callback is inlined away, so unobserved updates cost nothing extra.
+            for it in &mut self.items {
+                Self::update_one_item(
+                    &self.profile,
+                    &self.behaviors,
+                    &self.events,
+                    self.day,
+                    it,
+                    &mut |_| {},
+                );
+            }
+        } else {
+            self.update_observed();
+        }
+        self.events.tick(&self.profile);
+        self.day = self.day.saturating_add(1);
+    }
+
+    // Changes are buffered while the item is borrowed and reported once it is updated.
+    fn update_observed(&mut self) {
+        let day = self.day;
+        let mut changes = Vec::new();
+        for (index, it) in self.items.iter_mut().enumerate() {
+            for o in &mut self.observers {
+                o.before_update(day, index, it);
+            }
+            changes.clear();
+            Self::update_one_item(
+                &self.profile,
+                &self.behaviors,
+                &self.events,
+                day,
+                it,
+                &mut |c| changes.push(c),
+            );
+            for o in &mut self.observers {
+                for change in &changes {
+                    match change {
+                        Change::Clamped(bound) => o.clamped(day, index, it, *bound),
+                        Change::Expired => o.expired(day, index, it),
+                        Change::ConcertPassed => o.concert_passed(day, index, it),
+                    }
+                }
+                o.after_update(day, index, it);
+            }
+        }
+    }
+
+    fn update_one_item(
+        profile: &Profile,
+        behaviors: &[Box<dyn ItemBehavior>],
+        events: &EventRegistry,
+        day: u32,
+        it: &mut Item,
+        on: &mut impl FnMut(Change),
+    ) {
         // Requirements: Any item can be conjured (eg, "Conjured Aged Brie", "Conjured Backstage passes").
-        let (is_conjured, base_name) = split_conjured(it.name.as_str());
-        // Determine item kind
-        let kind: Kind = base_name.into();
+        // Other prefixes in `Profile::modifiers` (e.g. "Blessed ") stack the same way.
+        let (mods, base_name) = profile.split_modifiers(it.name.as_str());
+        // Custom kinds come first; with none registered this is a single emptiness check.
+        if !behaviors.is_empty()
+            && let Some(b) = find_behavior(behaviors, base_name)
+        {
+            let ctx = ItemContext {
+                day,
+                base_name,
+                mods,
+                profile,
+            };
+            let (sell_in, quality) = update_custom(b, &ctx, it.sell_in, it.quality, on);
+            (it.sell_in, it.quality) = (sell_in, quality);
+            return;
+        }
+        // Determine item kind (legendary registry first, then built-in names)
+        let kind = profile.classify(base_name);
         // Requirements: Legendary items do not change in quality or sell_in
-        if matches!(kind, Kind::Legendary) {
+        if let Kind::Legendary(quality) = kind {
+            if profile.legendary == LegendaryPolicy::Force {
+                it.quality = quality;
+            }
             return;
         }
+        // Passes for a registered event follow the event's date rather than their own
+        if matches!(kind, Kind::BackstagePass)
+            && let Some(status) = backstage_event(base_name).and_then(|e| events.status(e))
+        {
+            match status {
+                EventStatus::Scheduled { days_left } => it.sell_in = days_left,
+                EventStatus::Cancelled => {
+                    it.quality = 0;
+                    let was_expired = it.sell_in.is_negative();
+                    it.sell_in = profile.next_sell_in(it.sell_in);
+                    if !was_expired && it.sell_in.is_negative() {
+                        on(Change::Expired);
+                    }
+                    return;
+                }
+            }
+        }
         // In debug mode, assert preconditions for non-legendary items (0-50 quality)
         #[cfg(debug_assertions)]
         assert_preconditions(&kind, it.quality);
-        // Determine quality decrement delta (1 for normal, 2 for conjured)
-        let dec_delta = if is_conjured { 2 } else { 1 };
+        // Determine quality decrement delta (1 for normal, 2 for conjured, ...).
+        // Curves are evaluated once per day, so the expiry pass repeats today's step.
+        let dec_delta = if let Kind::Perishable(curve) = &kind {
+            mods.degrade(curve.step(it.sell_in, it.quality))
+        } else {
+            mods.degrade(1)
+        };
         // Update quality based on kind and sell_in
         match (&kind, it.sell_in) {
             // Requirements: Legendary items do not change in quality or sell_in
-            (Kind::Legendary, _) => {
+            (Kind::Legendary(_), _) => {
                 unreachable!("Legendary items should have been returned earlier")
             }
             // Requirements: Aged Brie increases in quality as it ages
-            (Kind::AgedBrie, _) => it.quality = inc_to_cap(it.quality, 1),
+            // Other appreciating goods may stop gaining from the sell-by date on (plateau/spoil).
+            (Kind::Appreciating(a), (..=0))
+                if !matches!(a.after_expiry, AfterExpiry::Continue { .. }) => {}
+            (Kind::Appreciating(a), _) => {
+                it.quality = inc_reported(it.quality, mods.appreciate(a.rate), on)
+            }
             // Requirements: Backstage
             // 1. Like Aged Brie, quality increases by 1 when there are more than 10 days left
             // 2. Quality increases by 2 when there are 10 days or less
             // 3. Quality increases by 3 when there are 5 days or less
             // 4. Quality drops to 0 after the concert
-            (Kind::BackstagePass, (11..)) => it.quality = inc_to_cap(it.quality, 1),
-            (Kind::BackstagePass, (6..=10)) => it.quality = inc_to_cap(it.quality, 2),
-            (Kind::BackstagePass, (1..=5)) => it.quality = inc_to_cap(it.quality, 3),
+            // Whether the thresholds are inclusive is a profile choice.
+            (Kind::BackstagePass, (1..)) => {
+                let inc = profile.backstage_increment(it.sell_in);
+                it.quality = inc_reported(it.quality, mods.appreciate(inc), on)
+            }
             (Kind::BackstagePass, (..=0)) => (), // Handled later in the expiry pass (after sell_in--)
             // Requirements: Normal items decrease in quality by 1 each day
-            (Kind::Normal, _) => it.quality = dec_to_floor(it.quality, dec_delta),
+            // (perishable goods by their curve's step instead)
+            (Kind::Normal | Kind::Perishable(_), _) => {
+                it.quality = dec_reported(it.quality, dec_delta, on)
+            }
         }
         // Decrease sell_in for all but legendary items
-        it.sell_in = it.sell_in.saturating_sub(1);
+        let was_expired = it.sell_in.is_negative();
+        it.sell_in = profile.next_sell_in(it.sell_in);
+        if !was_expired && it.sell_in.is_negative() {
+            on(Change::Expired);
+            if matches!(kind, Kind::BackstagePass) {
+                on(Change::ConcertPassed);
+            }
+        }
         // Handle expired items
         if it.sell_in.is_negative() {
             match kind {
                 // Requirements: Once the sell by date has passed, Quality degrades twice as fast
-                Kind::Normal => it.quality = dec_to_floor(it.quality, dec_delta),
-                Kind::AgedBrie => it.quality = inc_to_cap(it.quality, 1),
+                Kind::Normal | Kind::Perishable(_) => {
+                    it.quality = dec_reported(it.quality, dec_delta, on)
+                }
+                Kind::Appreciating(a) => match a.after_expiry {
+                    AfterExpiry::Continue { extra } => {
+                        it.quality = inc_reported(it.quality, mods.appreciate(extra), on)
+                    }
+                    AfterExpiry::Plateau => (),
+                    AfterExpiry::Spoil { rate } => {
+                        it.quality = dec_reported(it.quality, mods.degrade(rate), on)
+                    }
+                },
                 // Requirements: Backstage quality drops to 0 after the concert
                 Kind::BackstagePass => it.quality = 0,
-                Kind::Legendary => {
+                Kind::Legendary(_) => {
                     unreachable!("Legendary items should have been returned earlier");
                 }
             }
         }
     }
 }
+
+fn find_behavior<'b>(
+    behaviors: &'b [Box<dyn ItemBehavior>],
+    base_name: &str,
+) -> Option<&'b dyn ItemBehavior> {
+    behaviors
+        .iter()
+        .find(|b| b.classify(base_name))
+        .map(|b| b.as_ref())
+}
+
+// The built-in pipeline's order with a custom kind's rules; returns the new `(sell_in, quality)`.
+fn update_custom(
+    b: &dyn ItemBehavior,
+    ctx: &ItemContext<'_>,
+    sell_in: i32,
+    quality: i32,
+    on: &mut impl FnMut(Change),
+) -> (i32, i32) {
+    #[cfg(debug_assertions)]
+    if let Err(e) = b.validate(ctx, sell_in, quality) {
+        panic!("{}", e);
+    }
+    let bounds = b.quality_bounds(ctx);
+    let mut quality = clamp_reported(b.daily(ctx, sell_in, quality), &bounds, on);
+    let next = b.next_sell_in(ctx, sell_in);
+    if !sell_in.is_negative() && next.is_negative() {
+        on(Change::Expired);
+    }
+    if next.is_negative() {
+        quality = clamp_reported(b.expiry(ctx, next, quality), &bounds, on);
+    }
+    (next, quality)
+}
+
+#[inline]
+fn clamp_reported(q: i32, bounds: &RangeInclusive<i32>, on: &mut impl FnMut(Change)) -> i32 {
+    if q > *bounds.end() {
+        on(Change::Clamped(Bound::Cap));
+    } else if q < *bounds.start() {
+        on(Change::Clamped(Bound::Floor));
+    }
+    q.clamp(*bounds.start(), *bounds.end())
+}
+
+// What `update_one_item` reports to observers.
+enum Change {
+    Clamped(Bound),
+    Expired,
+    ConcertPassed,
+}
+
+// `inc_to_cap` / `dec_to_floor` that report when the cap or floor cut the change short.
+#[inline]
+fn inc_reported(q: i32, n: i32, on: &mut impl FnMut(Change)) -> i32 {
+    let capped = inc_to_cap(q, n);
+    if n > 0 && capped != q.saturating_add(n) {
+        on(Change::Clamped(Bound::Cap));
+    }
+    capped
+}
+
+#[inline]
+fn dec_reported(q: i32, n: i32, on: &mut impl FnMut(Change)) -> i32 {
+    let floored = dec_to_floor(q, n);
+    if n > 0 && floored != q.saturating_sub(n) {
+        on(Change::Clamped(Bound::Floor));
+    }
+    floored
+}
+
+// The event a Backstage pass belongs to, if its name names one.
+fn pass_event<'a>(profile: &Profile, name: &'a str) -> Option<&'a str> {
+    let (_, base_name) = profile.split_modifiers(name);
+    if !matches!(profile.classify(base_name), Kind::BackstagePass) {
+        return None;
+    }
+    backstage_event(base_name)
+}
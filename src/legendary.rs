@@ -0,0 +1,44 @@
+use crate::pattern;
+use std::borrow::Cow;
+
+pub const NAME_SULFURAS: &str = "Sulfuras, Hand of Ragnaros";
+pub const QUALITY_SULFURAS: i32 = 80;
+
+// A legendary item never changes and always has its own fixed quality.
+// `pattern` is an exact base name or a glob (see `pattern.rs`).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Legendary {
+    pub pattern: Cow<'static, str>,
+    pub quality: i32,
+}
+
+impl Legendary {
+    pub const fn new(pattern: &'static str, quality: i32) -> Legendary {
+        Legendary {
+            pattern: Cow::Borrowed(pattern),
+            quality,
+        }
+    }
+
+    // Parse `PATTERN=QUALITY`, e.g. `Thunderfury*=90`. The last `=` separates the quality.
+    pub fn parse(spec: &str) -> Option<Legendary> {
+        let (pattern, quality) = spec.rsplit_once('=')?;
+        if pattern.is_empty() {
+            return None;
+        }
+        Some(Legendary {
+            pattern: Cow::Owned(pattern.to_string()),
+            quality: quality.parse().ok()?,
+        })
+    }
+
+    #[must_use]
+    pub fn matches(&self, name: &str) -> bool {
+        pattern::matches(&self.pattern, name)
+    }
+}
+
+// Default registry: the kata's only legendary.
+pub fn builtin_legendaries() -> Vec<Legendary> {
+    vec![Legendary::new(NAME_SULFURAS, QUALITY_SULFURAS)]
+}
@@ -0,0 +1,34 @@
+pub mod advisor;
+pub mod alert;
+pub mod alias;
+pub mod appreciating;
+pub mod behavior;
+pub mod chart;
+pub mod cli;
+pub mod console;
+pub mod decay;
+pub mod demand;
+pub mod diff;
+pub mod event;
+pub mod explain;
+pub mod expr;
+pub mod gilded_rose;
+pub mod json;
+pub mod legendary;
+pub mod lint;
+pub mod modifier;
+pub mod observer;
+pub mod pattern;
+pub mod profile;
+pub mod render;
+pub mod rpc;
+pub mod sales;
+pub mod server;
+pub mod spec;
+pub mod stock;
+pub mod stores;
+pub mod supply;
+#[cfg(test)]
+mod unit_tests;
+
+pub use gilded_rose::{GildedRose, Item};
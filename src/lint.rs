@@ -0,0 +1,224 @@
+use crate::alias::AliasTarget;
+use crate::profile::Profile;
+use crate::spec::{Kind, PREFIX_BACKSTAGE, PREFIX_CONJURED};
+use std::fmt::{self, Display};
+
+// A name that is close to a special name or prefix but, since matching is exact, is
+// classified as a Normal item (or misses a modifier). `suggestion` is the corrected name.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NearMiss {
+    pub name: String,
+    pub suggestion: String,
+    // One per correction, e.g. `"Aged Brie": only case differs`.
+    pub reasons: Vec<String>,
+}
+
+impl Display for NearMiss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} looks like {:?} ({})",
+            self.name,
+            self.suggestion,
+            self.reasons.join("; ")
+        )
+    }
+}
+
+// Check one item name against the profile's modifiers, its registries' exact names and
+// literal glob prefixes, its aliases and the Backstage prefix. Names that already have a
+// special kind are only checked for misspelt modifiers.
+pub fn check(profile: &Profile, name: &str) -> Option<NearMiss> {
+    let mut suggestion = String::new();
+    let mut reasons = Vec::new();
+    let mut rest = name;
+    loop {
+        let (_, base_name) = profile.split_modifiers(rest);
+        suggestion.push_str(&rest[..rest.len() - base_name.len()]);
+        rest = base_name;
+        let Some((prefix, tail, reason)) = near_modifier(profile, rest) else {
+            break;
+        };
+        suggestion.push_str(prefix);
+        reasons.push(reason);
+        rest = tail;
+    }
+    let normal = matches!(profile.classify(rest), Kind::Normal);
+    match near_name(profile, rest).filter(|_| normal) {
+        Some((corrected, reason)) => {
+            suggestion.push_str(&corrected);
+            reasons.push(reason);
+        }
+        None => suggestion.push_str(rest),
+    }
+    if reasons.is_empty() {
+        return None;
+    }
+    Some(NearMiss {
+        name: name.to_string(),
+        suggestion,
+        reasons,
+    })
+}
+
+// `(prefix, rest of the name, reason)` if the first word is a misspelt modifier.
+fn near_modifier<'p, 'a>(
+    profile: &'p Profile,
+    name: &'a str,
+) -> Option<(&'p str, &'a str, String)> {
+    let start = name.len() - name.trim_start().len();
+    let end = name[start..]
+        .find(char::is_whitespace)
+        .map_or(name.len(), |i| start + i);
+    let tail = name[end..].trim_start();
+    if tail.is_empty() {
+        return None;
+    }
+    let conjured = profile.conjured.modifier().map(|_| PREFIX_CONJURED);
+    conjured
+        .into_iter()
+        .chain(profile.modifiers.iter().map(|m| m.prefix.as_ref()))
+        .filter_map(|prefix| {
+            let (edits, reason) = closeness(&name[..end], prefix.trim_end())?;
+            Some((edits, prefix, reason))
+        })
+        .min_by_key(|(edits, _, _)| *edits)
+        .map(|(_, prefix, reason)| (prefix, tail, reason))
+}
+
+// The corrected base name and the reason, for the closest known name or prefix.
+fn near_name(profile: &Profile, base_name: &str) -> Option<(String, String)> {
+    let mut best: Option<(usize, String, String)> = None;
+    let mut consider = |edits: usize, corrected: String, reason: String| {
+        if best.as_ref().is_none_or(|(e, _, _)| edits < *e) {
+            best = Some((edits, corrected, reason));
+        }
+    };
+    for known in known_names(profile) {
+        if let Some((edits, reason)) = closeness(base_name, known) {
+            consider(edits, known.to_string(), reason);
+        }
+    }
+    let normalized = normalize(base_name);
+    let chars: Vec<(usize, char)> = normalized.char_indices().collect();
+    for prefix in known_prefixes(profile) {
+        if base_name.starts_with(prefix) {
+            continue;
+        }
+        let len = prefix.chars().count();
+        let slack = max_edits(len);
+        // Try heads a few characters shorter or longer, to allow for dropped or extra letters;
+        // on a tie, prefer the one that ends at a word boundary.
+        let mut found: Option<(usize, bool, &str, String)> = None;
+        for n in len.saturating_sub(slack)..=(len + slack).min(chars.len()) {
+            let split = chars.get(n).map_or(normalized.len(), |&(i, _)| i);
+            let (head, tail) = normalized.split_at(split);
+            let close = if head == prefix {
+                Some((0, format!("{:?}: only whitespace differs", prefix)))
+            } else {
+                closeness(head, prefix)
+            };
+            let Some((edits, reason)) = close else {
+                continue;
+            };
+            let boundary = tail.is_empty() || tail.starts_with(' ');
+            if found
+                .as_ref()
+                .is_none_or(|(e, b, _, _)| (edits, !boundary) < (*e, !*b))
+            {
+                found = Some((edits, boundary, tail, reason));
+            }
+        }
+        if let Some((edits, _, tail, reason)) = found {
+            consider(edits, format!("{}{}", prefix, tail), reason);
+        }
+    }
+    best.map(|(_, corrected, reason)| (corrected, reason))
+}
+
+// Exact names from the registries and aliases, including alias targets.
+fn known_names(profile: &Profile) -> impl Iterator<Item = &str> {
+    let registries = profile
+        .legendaries
+        .iter()
+        .map(|l| l.pattern.as_ref())
+        .chain(profile.appreciating.iter().map(|a| a.pattern.as_ref()))
+        .chain(profile.perishables.iter().map(|p| p.pattern.as_ref()))
+        .chain(profile.aliases.iter().map(|a| a.pattern.as_str()));
+    let targets = profile.aliases.iter().filter_map(|a| match &a.target {
+        AliasTarget::Name(name) => Some(name.as_str()),
+        AliasTarget::Kind(_) => None,
+    });
+    registries.chain(targets).filter(|p| !p.contains('*'))
+}
+
+// The Backstage prefix and the literal part of `PREFIX*` globs.
+fn known_prefixes(profile: &Profile) -> impl Iterator<Item = &str> {
+    let globs = profile
+        .legendaries
+        .iter()
+        .map(|l| l.pattern.as_ref())
+        .chain(profile.appreciating.iter().map(|a| a.pattern.as_ref()))
+        .chain(profile.perishables.iter().map(|p| p.pattern.as_ref()))
+        .chain(profile.aliases.iter().map(|a| a.pattern.as_str()));
+    globs
+        .filter_map(|p| p.strip_suffix('*').filter(|head| !head.contains('*')))
+        .filter(|head| !head.is_empty())
+        .chain([PREFIX_BACKSTAGE])
+}
+
+// How far `s` is from `known`: `None` if they are equal or too far apart, otherwise the
+// number of edits (0 for case and whitespace) and a reason.
+fn closeness(s: &str, known: &str) -> Option<(usize, String)> {
+    if s == known {
+        return None;
+    }
+    let normalized = normalize(s);
+    if normalized == known {
+        return Some((0, format!("{:?}: only whitespace differs", known)));
+    }
+    let (lower, known_lower) = (normalized.to_lowercase(), known.to_lowercase());
+    if lower == known_lower {
+        let reason = if normalized == s {
+            "only case differs"
+        } else {
+            "only case and whitespace differ"
+        };
+        return Some((0, format!("{:?}: {}", known, reason)));
+    }
+    let edits = edit_distance(&lower, &known_lower);
+    (edits <= max_edits(known.chars().count())).then(|| {
+        let plural = if edits == 1 { "" } else { "s" };
+        (edits, format!("{:?}: {} edit{} away", known, edits, plural))
+    })
+}
+
+// Typos allowed for a name of `len` characters: none up to 7, then one more per 4, at most 3.
+#[must_use]
+#[inline]
+fn max_edits(len: usize) -> usize {
+    (len.saturating_sub(4) / 4).min(3)
+}
+
+// Trimmed, with every run of whitespace turned into one space.
+fn normalize(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Levenshtein distance over chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (above + 1)
+                .min(row[j] + 1)
+                .min(diagonal + usize::from(ca != cb));
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
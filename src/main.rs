@@ -1,12 +1,178 @@
-mod gilded_rose;
-mod spec;
-#[cfg(test)]
-mod unit_tests;
-
+use gilded_rose::advisor::Advisor;
+use gilded_rose::alert::{Alerts, FileSink, WriterSink};
+use gilded_rose::alias;
+use gilded_rose::chart;
+use gilded_rose::cli::{self, CliError, EventAction, Options, USAGE};
+use gilded_rose::console::{CONSOLE_HELP, Console};
+use gilded_rose::demand::{DemandRule, MonteCarlo};
+use gilded_rose::diff::{self, Diff, Matching};
+use gilded_rose::event::EventStatus;
+use gilded_rose::expr::RuleSet;
+use gilded_rose::json::Json;
+use gilded_rose::lint;
+use gilded_rose::render;
+use gilded_rose::rpc::Session;
+use gilded_rose::sales::{Cents, Ledger, Pricing, SalePolicy};
+use gilded_rose::server::Server;
 use gilded_rose::{GildedRose, Item};
 
 fn main() {
-    let items = vec![
+    let mut opts = match cli::parse(std::env::args().skip(1)) {
+        Ok(opts) => opts,
+        Err(CliError::Usage) => exit_with_usage(),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Some(path) = &opts.aliases {
+        match alias::load(path) {
+            Ok(aliases) => opts
+                .profile
+                .get_or_insert_with(Default::default)
+                .aliases
+                .extend(aliases),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if opts.rpc {
+        let mut session = Session::new(|items| build_rose(&opts, items));
+        if let Err(e) = session.run(std::io::stdin().lock(), std::io::stdout().lock()) {
+            eprintln!("rpc stopped: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if opts.repl {
+        let mut console = Console::new(|items| build_rose(&opts, items), sample_items());
+        println!("{}", CONSOLE_HELP);
+        if let Err(e) = console.run_terminal() {
+            eprintln!("console stopped: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Some((trials, seed)) = opts.simulate {
+        simulate(&opts, trials, seed);
+        return;
+    }
+    let mut rose = sample_rose(&opts);
+    warn_near_misses(&opts, &rose);
+    if let Some(selection) = &opts.chart {
+        let series = chart::select(chart::trajectories(&mut rose, DAYS), selection);
+        print!("{}", chart::svg("Quality by day", &series));
+        return;
+    }
+    if let Some(format) = opts.diff {
+        print_diffs(&mut rose, format);
+        return;
+    }
+    if let Some(format) = opts.report {
+        let days = render::simulate(&mut rose, DAYS);
+        print!("{}", render::render(format, "Gilded Rose inventory", &days));
+        return;
+    }
+    if let Some(addr) = &opts.serve {
+        serve(addr, rose);
+        return;
+    }
+    if let Some(horizon) = opts.advise {
+        let advisor = Advisor::new(Pricing::default(), SalePolicy::default(), horizon);
+        println!("sell schedule: day, name, sellIn, quality, price (today)");
+        for advice in advisor.schedule(&rose) {
+            println!("{}", advice);
+        }
+        return;
+    }
+    let mut ledger = Ledger::default();
+    let mut alerts = alerts(&opts);
+    let aliases = alias::usage(rose.profile(), &rose.items);
+    if !aliases.is_empty() {
+        println!("aliases: alias, language, target, items");
+        for used in aliases {
+            println!("{}", used);
+        }
+        println!();
+    }
+
+    for i in 0..DAYS {
+        println!("-------- day {} --------", i);
+        for (_, action) in opts.event_actions.iter().filter(|(day, _)| *day == i) {
+            apply_event_action(&mut rose, action);
+        }
+        let supply = opts.supply.start_day(&mut rose);
+        for delivery in &supply.received {
+            for spec in &delivery.items {
+                match &delivery.supplier {
+                    Some(supplier) => println!("delivered: {} from {}", spec, supplier),
+                    None => println!("delivered: {}", spec),
+                }
+            }
+        }
+        for (spec, reason) in &supply.rejected {
+            eprintln!("rejected delivery: {}: {}", spec, reason);
+        }
+        for order in &supply.ordered {
+            for spec in &order.items {
+                println!("ordered: {}, arriving day {}", spec, order.day);
+            }
+        }
+        println!("name, sellIn, quality");
+        for item in &rose.items {
+            println!("{}", item);
+        }
+        for (event, status) in rose.events().iter() {
+            match status {
+                EventStatus::Scheduled { days_left } => println!("event: {}, {}", event, days_left),
+                EventStatus::Cancelled => println!("event: {}, cancelled", event),
+            }
+        }
+        for (_, name) in opts.sales.iter().filter(|(day, _)| *day == i) {
+            sell_by_name(&mut rose, &mut ledger, name);
+        }
+        if ledger.day(i).next().is_some() {
+            println!("sales: receipt, day, name, quality, count x price");
+            for sale in ledger.day(i) {
+                println!("{}", sale);
+            }
+            println!("total: {}", Cents(ledger.day_total_cents(i)));
+        }
+        rose.update_quality();
+        let (_, errors) = alerts.check(&rose);
+        for e in errors {
+            eprintln!("alert delivery failed: {}", e);
+        }
+        println!();
+    }
+}
+
+const DAYS: u32 = 31;
+
+fn alerts(opts: &Options) -> Alerts {
+    let mut alerts = Alerts::new(opts.alerts.clone());
+    if opts.alerts.is_empty() {
+        return alerts;
+    }
+    alerts.add_sink(Box::new(WriterSink::new(std::io::stdout())));
+    if let Some(path) = &opts.alert_file {
+        alerts.add_sink(Box::new(FileSink::new(path)));
+    }
+    if let Some(webhook) = &opts.alert_webhook {
+        alerts.add_sink(Box::new(webhook.clone()));
+    }
+    alerts
+}
+
+fn sample_rose(opts: &Options) -> GildedRose {
+    build_rose(opts, sample_items())
+}
+
+fn sample_items() -> Vec<Item> {
+    vec![
         Item::new("+5 Dexterity Vest", 10, 20),
         Item::new("Aged Brie", 2, 0),
         Item::new("Elixir of the Mongoose", 5, 7),
@@ -15,16 +181,149 @@ fn main() {
         Item::new("Backstage passes to a TAFKAL80ETC concert", 15, 20),
         Item::new("Backstage passes to a TAFKAL80ETC concert", 10, 49),
         Item::new("Backstage passes to a TAFKAL80ETC concert", 5, 49),
-    ];
-    let mut rose = GildedRose::new(items);
+    ]
+}
 
-    for i in 0..=30 {
-        println!("-------- day {} --------", i);
-        println!("name, sellIn, quality");
-        for item in &rose.items {
-            println!("{}", item);
-        }
-        println!();
+// A `GildedRose` over `items` with the profile, rules and events from the command line.
+fn build_rose(opts: &Options, items: Vec<Item>) -> GildedRose {
+    let mut rose = match &opts.profile {
+        Some(profile) => GildedRose::with_profile(items, profile.clone()),
+        None => GildedRose::new(items),
+    };
+    if !opts.rules.is_empty() {
+        rose.add_behavior(Box::new(RuleSet::new(opts.rules.clone())));
+    }
+    for (event, days_left) in &opts.events {
+        rose.schedule_event(event, *days_left);
+    }
+    rose
+}
+
+// Without `--demand`, one customer per day on average for anything in stock.
+fn simulate(opts: &Options, trials: u32, seed: u64) {
+    let demand = if opts.demand.is_empty() {
+        vec![DemandRule::anything()]
+    } else {
+        opts.demand.clone()
+    };
+    let mut mc = MonteCarlo::new(seed, trials, DAYS, demand);
+    mc.preference = opts.preference;
+    let report = mc.run(|| sample_rose(opts), &opts.supply);
+    println!("{} trials, {} days, seed {}", trials, DAYS, seed);
+    println!("measure, min, p10, median, p90, max, mean");
+    let revenue = report.revenue_cents;
+    println!(
+        "revenue, {}, {}, {}, {}, {}, {}",
+        Cents(revenue.min),
+        Cents(revenue.p10),
+        Cents(revenue.median),
+        Cents(revenue.p90),
+        Cents(revenue.max),
+        Cents(revenue.mean)
+    );
+    for (measure, d) in [("waste", report.waste), ("stock-outs", report.stock_outs)] {
+        println!(
+            "{}, {}, {}, {}, {}, {}, {}",
+            measure, d.min, d.p10, d.median, d.p90, d.max, d.mean
+        );
+    }
+}
+
+// Each day's changes from the day before, as text sections or one JSON array of
+// `{"day", "added", "removed", "modified"}`.
+fn print_diffs(rose: &mut GildedRose, format: diff::Format) {
+    let mut days = Vec::new();
+    for _ in 0..DAYS {
+        let before: Vec<Item> = rose
+            .items
+            .iter()
+            .map(|it| Item::new(it.name.clone(), it.sell_in, it.quality))
+            .collect();
         rose.update_quality();
+        let changes = Diff::between(&before, &rose.items, Matching::Position);
+        match format {
+            diff::Format::Text => {
+                println!("-------- day {} --------\n{}\n", rose.day(), changes)
+            }
+            diff::Format::Json => {
+                let mut fields = vec![("day".to_string(), rose.day().into())];
+                if let Json::Object(rest) = changes.to_json() {
+                    fields.extend(rest);
+                }
+                days.push(Json::Object(fields));
+            }
+        }
+    }
+    if format == diff::Format::Json {
+        println!("{}", Json::Array(days));
+    }
+}
+
+// Near-miss names in the stock and on the command line, on stderr; the run goes on.
+fn warn_near_misses(opts: &Options, rose: &GildedRose) {
+    for (index, near) in rose.lint() {
+        eprintln!("warning: item {}: {}", index, near);
+    }
+    let delivered = opts
+        .supply
+        .pending()
+        .iter()
+        .flat_map(|d| &d.items)
+        .map(|spec| spec.name.as_str());
+    let sold = opts.sales.iter().map(|(_, name)| name.as_str());
+    for name in delivered.chain(sold) {
+        if let Some(near) = lint::check(rose.profile(), name) {
+            eprintln!("warning: {}", near);
+        }
+    }
+}
+
+fn serve(addr: &str, rose: GildedRose) {
+    let mut server = match Server::bind(addr, rose) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("cannot listen on {}: {}", addr, e);
+            std::process::exit(1);
+        }
+    };
+    if let Ok(local) = server.local_addr() {
+        println!("listening on http://{}", local);
+    }
+    if let Err(e) = server.serve() {
+        eprintln!("server stopped: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn apply_event_action(rose: &mut GildedRose, action: &EventAction) {
+    match action {
+        EventAction::Postpone { event, days } => match rose.postpone_event(event, *days) {
+            Ok(()) => println!("postponed {} by {} days", event, days),
+            Err(e) => eprintln!("{}", e),
+        },
+        EventAction::Cancel { event } => match rose.cancel_event(event) {
+            Ok(refunds) => {
+                println!("cancelled {}", event);
+                for r in refunds {
+                    println!("refund: {}, {}", r.name, r.quality);
+                }
+            }
+            Err(e) => eprintln!("{}", e),
+        },
     }
 }
+
+fn sell_by_name(rose: &mut GildedRose, ledger: &mut Ledger, name: &str) {
+    let Some(index) = rose.items.iter().position(|it| it.name == name) else {
+        eprintln!("nothing to sell: {}", name);
+        return;
+    };
+    if let Err(e) = ledger.sell(rose, index) {
+        eprintln!("{}", e);
+    }
+}
+
+fn exit_with_usage() -> ! {
+    eprintln!("{USAGE}");
+    std::process::exit(2);
+}
@@ -0,0 +1,177 @@
+use crate::spec::PREFIX_CONJURED;
+use std::borrow::Cow;
+
+// Modifiers are name prefixes that act as item properties (like "Conjured "), not as kinds.
+// Each one transforms the size of a quality change; the base name still decides the kind.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Modifier {
+    // Includes the trailing space, e.g. "Blessed ".
+    pub prefix: Cow<'static, str>,
+    // Applied to decreases (Normal items, spoiling).
+    pub degrade: Step,
+    // Applied to increases (Aged Brie, Backstage bands).
+    pub appreciate: Step,
+}
+
+// A linear transform of a step size: `n * factor + offset`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Step {
+    pub factor: i32,
+    pub offset: i32,
+}
+
+impl Step {
+    pub const IDENTITY: Step = Step {
+        factor: 1,
+        offset: 0,
+    };
+
+    pub const fn times(factor: i32) -> Step {
+        Step { factor, offset: 0 }
+    }
+
+    pub const fn plus(offset: i32) -> Step {
+        Step { factor: 1, offset }
+    }
+
+    // `self` first, then `next`: (n * f1 + o1) * f2 + o2.
+    #[must_use]
+    pub fn then(self, next: Step) -> Step {
+        Step {
+            factor: self.factor.saturating_mul(next.factor),
+            offset: self
+                .offset
+                .saturating_mul(next.factor)
+                .saturating_add(next.offset),
+        }
+    }
+
+    // A stack can shrink a change to 0 but never flips its direction.
+    #[must_use]
+    #[inline]
+    pub fn apply(self, n: i32) -> i32 {
+        n.saturating_mul(self.factor)
+            .saturating_add(self.offset)
+            .max(0)
+    }
+}
+
+impl Modifier {
+    pub const fn new(prefix: &'static str, degrade: Step, appreciate: Step) -> Modifier {
+        Modifier {
+            prefix: Cow::Borrowed(prefix),
+            degrade,
+            appreciate,
+        }
+    }
+
+    // Degrades `factor` times as fast; gains are scaled too only when `amplify_gains` is set.
+    // The README's degrade-only policy is `conjured(2, false)`.
+    pub const fn conjured(factor: i32, amplify_gains: bool) -> Modifier {
+        let appreciate = if amplify_gains {
+            Step::times(factor)
+        } else {
+            Step::IDENTITY
+        };
+        Modifier::new(PREFIX_CONJURED, Step::times(factor), appreciate)
+    }
+
+    // Loses one less per step and gains one more.
+    pub const fn blessed() -> Modifier {
+        Modifier::new("Blessed ", Step::plus(-1), Step::plus(1))
+    }
+
+    // Loses one more per step and gains one less.
+    pub const fn cursed() -> Modifier {
+        Modifier::new("Cursed ", Step::plus(1), Step::plus(-1))
+    }
+
+    // Gains twice as fast; losses are untouched.
+    pub const fn enchanted() -> Modifier {
+        Modifier::new("Enchanted ", Step::IDENTITY, Step::times(2))
+    }
+}
+
+// The opt-in modifiers by their command-line name: `blessed`, `cursed` or `enchanted`.
+pub fn by_name(name: &str) -> Option<Modifier> {
+    match name {
+        "blessed" => Some(Modifier::blessed()),
+        "cursed" => Some(Modifier::cursed()),
+        "enchanted" => Some(Modifier::enchanted()),
+        _ => None,
+    }
+}
+
+// Blessed, Cursed and Enchanted. Not in any built-in profile: items with these prefixes stay
+// Normal unless a shop turns the modifiers on.
+pub fn builtin_modifiers() -> Vec<Modifier> {
+    vec![
+        Modifier::blessed(),
+        Modifier::cursed(),
+        Modifier::enchanted(),
+    ]
+}
+
+// The combined effect of all modifiers found on a name.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Modifiers {
+    pub degrade: Step,
+    pub appreciate: Step,
+    // Number of "Conjured " prefixes seen (kept for reporting).
+    pub conjured: u32,
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers {
+        degrade: Step::IDENTITY,
+        appreciate: Step::IDENTITY,
+        conjured: 0,
+    };
+
+    #[must_use]
+    #[inline]
+    pub fn degrade(&self, n: i32) -> i32 {
+        self.degrade.apply(n)
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn appreciate(&self, n: i32) -> i32 {
+        self.appreciate.apply(n)
+    }
+
+    fn push(&mut self, m: &Modifier) {
+        self.degrade = self.degrade.then(m.degrade);
+        self.appreciate = self.appreciate.then(m.appreciate);
+    }
+}
+
+// Strip known prefixes left to right, any number of times and in any order.
+// Modifiers are applied in the order they appear, e.g. "Cursed Conjured foo" loses (1 + 1) * 2 = 4.
+#[must_use]
+pub fn split_modifiers<'a>(
+    name: &'a str,
+    conjured: Option<&Modifier>,
+    table: &[Modifier],
+) -> (Modifiers, &'a str) {
+    let mut mods = Modifiers::NONE;
+    let mut rest = name;
+    'outer: loop {
+        if let Some(c) = conjured
+            && let Some(r) = rest.strip_prefix(c.prefix.as_ref())
+        {
+            mods.push(c);
+            mods.conjured += 1;
+            rest = r;
+            continue;
+        }
+        for m in table {
+            if let Some(r) = rest.strip_prefix(m.prefix.as_ref()) {
+                mods.push(m);
+                rest = r;
+                continue 'outer;
+            }
+        }
+        return (mods, rest);
+    }
+}
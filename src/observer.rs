@@ -0,0 +1,32 @@
+use crate::gilded_rose::Item;
+
+// Which limit a quality change ran into.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Bound {
+    // `QUALITY_MAX`, see `inc_to_cap`.
+    Cap,
+    // `QUALITY_MIN`, see `dec_to_floor`.
+    Floor,
+}
+
+// Hooks into `GildedRose::update_quality`, registered with `add_observer`. Every callback has
+// an empty default, so an observer implements only what it needs. `day` is `GildedRose::day()`
+// before the update and `index` the item's position in `items`.
+//
+// Per item the order is `before_update`, then any of `clamped` / `expired` /
+// `concert_passed` in the order they happened, then `after_update`; these see the updated item.
+// Legendary items get only `before_update` and `after_update`.
+pub trait Observer {
+    fn before_update(&mut self, _day: u32, _index: usize, _it: &Item) {}
+
+    fn after_update(&mut self, _day: u32, _index: usize, _it: &Item) {}
+
+    // `sell_in` crossed below 0 in this update.
+    fn expired(&mut self, _day: u32, _index: usize, _it: &Item) {}
+
+    // A quality change was cut short by the cap or the floor.
+    fn clamped(&mut self, _day: u32, _index: usize, _it: &Item, _bound: Bound) {}
+
+    // A Backstage pass's concert day has passed (reported right after `expired`).
+    fn concert_passed(&mut self, _day: u32, _index: usize, _it: &Item) {}
+}
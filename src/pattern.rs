@@ -0,0 +1,75 @@
+use crate::gilded_rose::GildedRose;
+
+// Name patterns used by the registries: an exact name, or a glob where `*` matches any
+// run of characters. Matching is case-sensitive with no trimming, like the built-in names.
+#[must_use]
+pub fn matches(pattern: &str, name: &str) -> bool {
+    if pattern.contains('*') {
+        glob(pattern, name)
+    } else {
+        pattern == name
+    }
+}
+
+fn glob(pattern: &str, name: &str) -> bool {
+    let (p, n) = (pattern.as_bytes(), name.as_bytes());
+    let (mut pi, mut ni) = (0, 0);
+    // Position of the last `*` and the name index it was tried at, for backtracking.
+    let mut star: Option<(usize, usize)> = None;
+    while ni < n.len() {
+        if pi < p.len() && p[pi] == b'*' {
+            star = Some((pi, ni));
+            pi += 1;
+        } else if pi < p.len() && p[pi] == n[ni] {
+            pi += 1;
+            ni += 1;
+        } else if let Some((sp, sn)) = star {
+            // Let the last `*` swallow one more byte and retry.
+            pi = sp + 1;
+            ni = sn + 1;
+            star = Some((sp, sn + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == b'*')
+}
+
+// Which items a rule counts: names matching a pattern (see `matches`), or `kind:LABEL` for
+// every item of a kind, compared with `GildedRose::kind_label` (`normal`, `appreciating`,
+// `backstage`, `legendary`, `perishable` or `custom`).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Selector {
+    Name(String),
+    Kind(&'static str),
+}
+
+impl Selector {
+    pub fn parse(s: &str) -> Option<Selector> {
+        match s.strip_prefix("kind:") {
+            Some(label) => KIND_LABELS
+                .iter()
+                .find(|&&l| l == label)
+                .map(|&l| Selector::Kind(l)),
+            None if s.is_empty() => None,
+            None => Some(Selector::Name(s.to_string())),
+        }
+    }
+
+    #[must_use]
+    pub fn matches(&self, rose: &GildedRose, name: &str) -> bool {
+        match self {
+            Selector::Name(pattern) => matches(pattern, name),
+            Selector::Kind(label) => rose.kind_label(name) == *label,
+        }
+    }
+}
+
+const KIND_LABELS: [&str; 6] = [
+    "normal",
+    "appreciating",
+    "backstage",
+    "legendary",
+    "perishable",
+    "custom",
+];
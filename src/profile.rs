@@ -0,0 +1,215 @@
+use crate::alias::{Alias, AliasTarget};
+use crate::appreciating::{Appreciating, builtin_appreciating};
+use crate::decay::Perishable;
+use crate::legendary::{Legendary, builtin_legendaries};
+use crate::modifier::{Modifier, Modifiers, split_modifiers};
+use crate::spec::Kind;
+
+// A profile collects the interpretation choices that the written spec leaves open.
+// `Profile::default()` is the interpretation documented in the README; other shops
+// pick a different profile instead of forking `update_one_item`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Profile {
+    pub backstage_thresholds: Thresholds,
+    pub conjured: ConjuredPolicy,
+    pub sell_in: SellInPolicy,
+    pub legendary: LegendaryPolicy,
+    // Known name prefixes besides "Conjured " (which is governed by `conjured`); empty by
+    // default, see `modifier::builtin_modifiers`.
+    pub modifiers: Vec<Modifier>,
+    // Legendary registry, consulted before the built-in name rules. First match wins.
+    pub legendaries: Vec<Legendary>,
+    // Appreciating goods registry (Aged Brie is built in), consulted after legendaries.
+    pub appreciating: Vec<Appreciating>,
+    // Perishable goods registry with per-name decay curves, consulted after appreciating goods.
+    pub perishables: Vec<Perishable>,
+    // Other names for known items, consulted before everything else. First match wins.
+    pub aliases: Vec<Alias>,
+}
+
+// How "10 days or less" / "5 days or less" is read for Backstage passes.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Thresholds {
+    // ≤10 and ≤5 (README: Requirements-first)
+    Inclusive,
+    // <10 and <5 (the original implementation)
+    Exclusive,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ConjuredPolicy {
+    // "Conjured " is not special; the full name is classified as-is.
+    Ignored,
+    // Conjured doubles negative quality changes only (README: degrade-only).
+    DegradeOnly,
+    // Conjured doubles every quality change, including Brie and Backstage gains.
+    AllChanges,
+    // Conjured scales decreases by `factor`, and increases too when `amplify_gains` is set.
+    Custom { factor: i32, amplify_gains: bool },
+}
+
+impl ConjuredPolicy {
+    // Parse `ignored`, `degrade-only[:N]` or `all-changes[:N]` (N = custom factor).
+    pub fn parse(s: &str) -> Option<Self> {
+        let (name, factor) = match s.split_once(':') {
+            Some((name, n)) => (name, Some(n.parse::<i32>().ok().filter(|n| *n >= 0)?)),
+            None => (s, None),
+        };
+        match (name, factor) {
+            ("ignored", None) => Some(ConjuredPolicy::Ignored),
+            ("degrade-only", None) => Some(ConjuredPolicy::DegradeOnly),
+            ("all-changes", None) => Some(ConjuredPolicy::AllChanges),
+            ("degrade-only", Some(factor)) => Some(ConjuredPolicy::Custom {
+                factor,
+                amplify_gains: false,
+            }),
+            ("all-changes", Some(factor)) => Some(ConjuredPolicy::Custom {
+                factor,
+                amplify_gains: true,
+            }),
+            _ => None,
+        }
+    }
+
+    // The modifier "Conjured " stands for under this policy, if it is recognized at all.
+    #[must_use]
+    pub const fn modifier(self) -> Option<Modifier> {
+        match self {
+            ConjuredPolicy::Ignored => None,
+            ConjuredPolicy::DegradeOnly => Some(Modifier::conjured(2, false)),
+            ConjuredPolicy::AllChanges => Some(Modifier::conjured(2, true)),
+            ConjuredPolicy::Custom {
+                factor,
+                amplify_gains,
+            } => Some(Modifier::conjured(factor, amplify_gains)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SellInPolicy {
+    // `sell_in` stays at `i32::MIN` instead of underflowing.
+    Saturating,
+    // `sell_in` wraps to `i32::MAX` like the original release build.
+    Wrapping,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LegendaryPolicy {
+    // Keep whatever quality the input provides (checked by debug assertions only).
+    Keep,
+    // Reset legendary quality to its registered value on every update.
+    Force,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile {
+            backstage_thresholds: Thresholds::Inclusive,
+            conjured: ConjuredPolicy::DegradeOnly,
+            sell_in: SellInPolicy::Saturating,
+            legendary: LegendaryPolicy::Keep,
+            modifiers: Vec::new(),
+            legendaries: builtin_legendaries(),
+            appreciating: builtin_appreciating(),
+            perishables: Vec::new(),
+            aliases: Vec::new(),
+        }
+    }
+}
+
+impl Profile {
+    // The rules as the original kata code implemented them, before Conjured support.
+    pub fn classic_kata() -> Self {
+        Profile {
+            backstage_thresholds: Thresholds::Exclusive,
+            conjured: ConjuredPolicy::Ignored,
+            sell_in: SellInPolicy::Wrapping,
+            legendary: LegendaryPolicy::Keep,
+            modifiers: Vec::new(),
+            legendaries: builtin_legendaries(),
+            appreciating: builtin_appreciating(),
+            perishables: Vec::new(),
+            aliases: Vec::new(),
+        }
+    }
+
+    // Look up a profile by the name used on the command line.
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Profile::default()),
+            "classic" => Some(Profile::classic_kata()),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn split_modifiers<'a>(&self, name: &'a str) -> (Modifiers, &'a str) {
+        split_modifiers(name, self.conjured.modifier().as_ref(), &self.modifiers)
+    }
+
+    // Classify a base name (modifiers already stripped). An alias stands for its target; a
+    // target name is not looked up as an alias again.
+    #[must_use]
+    pub fn classify(&self, base_name: &str) -> Kind<'_> {
+        match self.alias(base_name).map(|a| &a.target) {
+            Some(AliasTarget::Name(name)) => self.classify_canonical(name),
+            Some(AliasTarget::Kind(kind)) => kind.kind(),
+            None => self.classify_canonical(base_name),
+        }
+    }
+
+    // The alias a base name is classified through, if any.
+    #[must_use]
+    #[inline]
+    pub fn alias(&self, base_name: &str) -> Option<&Alias> {
+        self.aliases.iter().find(|a| a.matches(base_name))
+    }
+
+    fn classify_canonical(&self, base_name: &str) -> Kind<'_> {
+        if let Some(l) = self.legendaries.iter().find(|l| l.matches(base_name)) {
+            return Kind::Legendary(l.quality);
+        }
+        if let Some(a) = self.appreciating.iter().find(|a| a.matches(base_name)) {
+            return Kind::Appreciating(a.rule);
+        }
+        if let Some(p) = self.perishables.iter().find(|p| p.matches(base_name)) {
+            return Kind::Perishable(&p.curve);
+        }
+        base_name.into()
+    }
+
+    // Classify a full item name: modifiers first, then the base name.
+    #[must_use]
+    pub fn kind_of(&self, name: &str) -> Kind<'_> {
+        let (_, base_name) = self.split_modifiers(name);
+        self.classify(base_name)
+    }
+
+    // Backstage increment for a pass that is not yet past the concert (`sell_in >= 1`).
+    #[must_use]
+    #[inline]
+    pub fn backstage_increment(&self, sell_in: i32) -> i32 {
+        let (near, nearer) = match self.backstage_thresholds {
+            Thresholds::Inclusive => (10, 5),
+            Thresholds::Exclusive => (9, 4),
+        };
+        if sell_in <= nearer {
+            3
+        } else if sell_in <= near {
+            2
+        } else {
+            1
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn next_sell_in(&self, sell_in: i32) -> i32 {
+        match self.sell_in {
+            SellInPolicy::Saturating => sell_in.saturating_sub(1),
+            SellInPolicy::Wrapping => sell_in.wrapping_sub(1),
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use crate::gilded_rose::GildedRose;
+use crate::spec::QUALITY_MIN;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+impl Format {
+    // Parse `md`, `markdown` or `html`.
+    pub fn parse(s: &str) -> Option<Format> {
+        match s {
+            "md" | "markdown" => Some(Format::Markdown),
+            "html" => Some(Format::Html),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ReportRow {
+    pub name: String,
+    pub sell_in: i32,
+    pub quality: i32,
+    // Highlighted rows; legendary items never expire and are never flagged.
+    pub expired: bool,
+    pub worthless: bool,
+}
+
+// The inventory on one day, grouped by kind.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DayReport {
+    pub day: u32,
+    // `(GildedRose::kind_label, rows in inventory order)`, labels in alphabetical order.
+    pub groups: Vec<(&'static str, Vec<ReportRow>)>,
+}
+
+impl DayReport {
+    pub fn of(rose: &GildedRose) -> DayReport {
+        let mut groups: Vec<(&'static str, Vec<ReportRow>)> = Vec::new();
+        for it in &rose.items {
+            let label = rose.kind_label(&it.name);
+            let perishable = label != "legendary";
+            let row = ReportRow {
+                name: it.name.clone(),
+                sell_in: it.sell_in,
+                quality: it.quality,
+                expired: perishable && it.sell_in.is_negative(),
+                worthless: perishable && it.quality <= QUALITY_MIN,
+            };
+            match groups.binary_search_by_key(&label, |(l, _)| l) {
+                Ok(i) => groups[i].1.push(row),
+                Err(i) => groups.insert(i, (label, vec![row])),
+            }
+        }
+        DayReport {
+            day: rose.day(),
+            groups,
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &ReportRow> {
+        self.groups.iter().flat_map(|(_, rows)| rows)
+    }
+
+    pub fn totals(&self) -> Totals {
+        let mut totals = Totals::default();
+        for row in self.rows() {
+            totals.items += 1;
+            totals.quality += i64::from(row.quality);
+            totals.expired += usize::from(row.expired);
+            totals.worthless += usize::from(row.worthless);
+        }
+        totals
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Totals {
+    pub items: usize,
+    pub quality: i64,
+    pub expired: usize,
+    // Items at quality 0.
+    pub worthless: usize,
+}
+
+// The report of every day from today on: the listing, then `update_quality`, `days` times.
+pub fn simulate(rose: &mut GildedRose, days: u32) -> Vec<DayReport> {
+    (0..days)
+        .map(|_| {
+            let report = DayReport::of(rose);
+            rose.update_quality();
+            report
+        })
+        .collect()
+}
+
+pub fn render(format: Format, title: &str, days: &[DayReport]) -> String {
+    match format {
+        Format::Markdown => markdown(title, days),
+        Format::Html => html(title, days),
+    }
+}
+
+// One `##` section per day, one table per kind; flagged rows get a bold status.
+pub fn markdown(title: &str, days: &[DayReport]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {}", md_escape(title));
+    for day in days {
+        let _ = writeln!(out, "\n## Day {}", day.day);
+        for (label, rows) in &day.groups {
+            let _ = writeln!(out, "\n### {}\n", label);
+            let _ = writeln!(out, "| Name | Sell in | Quality | Status |");
+            let _ = writeln!(out, "|---|---:|---:|---|");
+            for row in rows {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {} | {} |",
+                    md_escape(&row.name),
+                    row.sell_in,
+                    row.quality,
+                    status(row).map_or(String::new(), |s| format!("**{}**", s))
+                );
+            }
+        }
+        let t = day.totals();
+        let _ = writeln!(
+            out,
+            "\n**Totals:** {} items, quality {}, {} expired, {} at quality 0",
+            t.items, t.quality, t.expired, t.worthless
+        );
+    }
+    out
+}
+
+// A standalone page with inline CSS; flagged rows carry the `expired` / `worthless` classes.
+pub fn html(title: &str, days: &[DayReport]) -> String {
+    let title = html_escape(title);
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>\n\
+         body {{ font-family: sans-serif; margin: 2em; }}\n\
+         table {{ border-collapse: collapse; margin-bottom: 1em; }}\n\
+         th, td {{ border: 1px solid #ccc; padding: 0.2em 0.6em; }}\n\
+         td.num {{ text-align: right; }}\n\
+         tr.expired {{ background: #fde2e1; }}\n\
+         tr.worthless {{ color: #999; }}\n\
+         </style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        title, title
+    );
+    for day in days {
+        let _ = writeln!(out, "<section>\n<h2>Day {}</h2>", day.day);
+        for (label, rows) in &day.groups {
+            let _ = writeln!(out, "<h3>{}</h3>\n<table>", label);
+            let _ = writeln!(
+                out,
+                "<tr><th>Name</th><th>Sell in</th><th>Quality</th><th>Status</th></tr>"
+            );
+            for row in rows {
+                let mut classes = Vec::new();
+                if row.expired {
+                    classes.push("expired");
+                }
+                if row.worthless {
+                    classes.push("worthless");
+                }
+                let class = if classes.is_empty() {
+                    String::new()
+                } else {
+                    format!(" class=\"{}\"", classes.join(" "))
+                };
+                let _ = writeln!(
+                    out,
+                    "<tr{}><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td>{}</td></tr>",
+                    class,
+                    html_escape(&row.name),
+                    row.sell_in,
+                    row.quality,
+                    status(row).unwrap_or_default()
+                );
+            }
+            let _ = writeln!(out, "</table>");
+        }
+        let t = day.totals();
+        let _ = writeln!(
+            out,
+            "<p><strong>Totals:</strong> {} items, quality {}, {} expired, {} at quality 0</p>\n</section>",
+            t.items, t.quality, t.expired, t.worthless
+        );
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn status(row: &ReportRow) -> Option<&'static str> {
+    match (row.expired, row.worthless) {
+        (true, true) => Some("expired, quality 0"),
+        (true, false) => Some("expired"),
+        (false, true) => Some("quality 0"),
+        (false, false) => None,
+    }
+}
+
+// Keep item names from breaking out of a table cell or starting inline markup.
+fn md_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '|' | '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+// Also fine for SVG text and attributes.
+pub(crate) fn html_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
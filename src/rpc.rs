@@ -0,0 +1,385 @@
+use crate::demand::{DemandRule, Distribution, MonteCarlo, Preference};
+use crate::explain::Explanation;
+use crate::gilded_rose::{GildedRose, Item};
+use crate::json::Json;
+use crate::server::{item_json, query_json};
+use crate::supply::Supply;
+use std::fmt::{self, Display};
+use std::io::{self, BufRead, Write};
+
+// Upper bounds for `simulate`, so one request cannot keep the engine busy for hours.
+const MAX_TRIALS: u32 = 10_000;
+const MAX_DAYS: u32 = 3_650;
+const MAX_TICKS: u32 = 3_650;
+
+// Errors with their JSON-RPC 2.0 codes. The codes are part of the protocol: existing ones
+// never change meaning, new ones get new numbers.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RpcError {
+    // -32700: the line is not JSON.
+    Parse,
+    // -32600: JSON, but not a request object.
+    InvalidRequest,
+    // -32601
+    MethodNotFound(String),
+    // -32602: missing or mistyped parameters.
+    InvalidParams(String),
+    // -32001
+    NoSuchItem(usize),
+    // -32002: `load` was given items breaking their kind's preconditions.
+    InvalidItems(Vec<(usize, String)>),
+}
+
+impl RpcError {
+    #[must_use]
+    pub fn code(&self) -> i64 {
+        match self {
+            RpcError::Parse => -32700,
+            RpcError::InvalidRequest => -32600,
+            RpcError::MethodNotFound(_) => -32601,
+            RpcError::InvalidParams(_) => -32602,
+            RpcError::NoSuchItem(_) => -32001,
+            RpcError::InvalidItems(_) => -32002,
+        }
+    }
+
+    fn to_json(&self) -> Json {
+        let mut fields = vec![
+            ("code".to_string(), Json::Int(self.code())),
+            ("message".to_string(), self.to_string().into()),
+        ];
+        if let RpcError::InvalidItems(invalid) = self {
+            fields.push(("data".to_string(), invalid_json(invalid)));
+        }
+        Json::Object(fields)
+    }
+}
+
+impl Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcError::Parse => write!(f, "parse error"),
+            RpcError::InvalidRequest => write!(f, "invalid request"),
+            RpcError::MethodNotFound(method) => write!(f, "method not found: {}", method),
+            RpcError::InvalidParams(reason) => write!(f, "invalid params: {}", reason),
+            RpcError::NoSuchItem(index) => write!(f, "no such item: {}", index),
+            RpcError::InvalidItems(invalid) => write!(f, "{} invalid items", invalid.len()),
+        }
+    }
+}
+
+// `[{"index", "reason"}]`
+fn invalid_json(invalid: &[(usize, String)]) -> Json {
+    Json::Array(
+        invalid
+            .iter()
+            .map(|(index, reason)| {
+                Json::object([
+                    ("index", (*index).into()),
+                    ("reason", reason.as_str().into()),
+                ])
+            })
+            .collect(),
+    )
+}
+
+// One line-delimited JSON-RPC 2.0 session over an inventory. `make` builds a `GildedRose`
+// with the session's profile, rules and events around the items given to `load`, and again
+// for every `simulate` trial.
+//
+// | Method     | Params                                             | Result                     |
+// |------------|----------------------------------------------------|----------------------------|
+// | `load`     | `{"items": [{"name", "sell_in", "quality"}]}`      | `{"items": N}`             |
+// | `tick`     | `{"days"?: N}` (default 1)                         | `{"day", "items"}`         |
+// | `simulate` | `{"trials", "seed", "days"?, "demand"?, "prefer"?}`| distributions              |
+// | `query`    | `{"name"?: GLOB, "kind"?: LABEL}`                  | items                      |
+// | `validate` | none                                               | `[{"index", "reason"}]`    |
+// | `explain`  | `{"index"}`                                        | `Explanation`              |
+//
+// `validate` also lists near-miss names (see `lint.rs`) with a `"suggestion"`; `load` only
+// refuses items that break their kind's preconditions.
+pub struct Session<F: Fn(Vec<Item>) -> GildedRose> {
+    make: F,
+    rose: GildedRose,
+}
+
+impl<F: Fn(Vec<Item>) -> GildedRose> Session<F> {
+    // Starts with an empty inventory.
+    pub fn new(make: F) -> Session<F> {
+        Session {
+            rose: make(Vec::new()),
+            make,
+        }
+    }
+
+    pub fn rose(&self) -> &GildedRose {
+        &self.rose
+    }
+
+    // Answer one line; `None` for a notification (no `id`) or a blank line.
+    pub fn handle_line(&mut self, line: &str) -> Option<String> {
+        if line.trim().is_empty() {
+            return None;
+        }
+        let (id, result) = match Json::parse(line) {
+            Ok(request) => {
+                let id = request.get("id").cloned();
+                let result = self.call(&request);
+                // Notifications are answered only when they are not requests at all.
+                if id.is_none() && result != Err(RpcError::InvalidRequest) {
+                    return None;
+                }
+                (id.unwrap_or(Json::Null), result)
+            }
+            Err(_) => (Json::Null, Err(RpcError::Parse)),
+        };
+        let outcome = match result {
+            Ok(value) => ("result", value),
+            Err(e) => ("error", e.to_json()),
+        };
+        let response = Json::object([("jsonrpc", "2.0".into()), ("id", id), outcome]);
+        Some(response.to_string())
+    }
+
+    // Answer every line of `input` on `output` until end of input.
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            if let Some(response) = self.handle_line(&line?) {
+                writeln!(output, "{}", response)?;
+                output.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn call(&mut self, request: &Json) -> Result<Json, RpcError> {
+        let (Some("2.0"), Some(method)) = (
+            request.get("jsonrpc").and_then(Json::as_str),
+            request.get("method").and_then(Json::as_str),
+        ) else {
+            return Err(RpcError::InvalidRequest);
+        };
+        let params = match request.get("params") {
+            None => &Json::Object(Vec::new()),
+            Some(params @ Json::Object(_)) => params,
+            Some(_) => return Err(RpcError::InvalidParams("params must be an object".into())),
+        };
+        match method {
+            "load" => self.load(params),
+            "tick" => self.tick(params),
+            "simulate" => self.simulate(params),
+            "query" => Ok(query_json(
+                &self.rose,
+                optional_str(params, "name")?,
+                optional_str(params, "kind")?,
+            )),
+            "validate" => {
+                let mut found = invalid_json(&self.rose.validate());
+                if let Json::Array(entries) = &mut found {
+                    entries.extend(self.rose.lint().into_iter().map(|(index, near)| {
+                        Json::object([
+                            ("index", index.into()),
+                            (
+                                "reason",
+                                format!("near miss: {}", near.reasons.join("; ")).into(),
+                            ),
+                            ("suggestion", near.suggestion.into()),
+                        ])
+                    }));
+                }
+                Ok(found)
+            }
+            "explain" => {
+                let index = required_int(params, "index")?;
+                Explanation::of(&self.rose, index)
+                    .map(|e| e.to_json())
+                    .ok_or(RpcError::NoSuchItem(index))
+            }
+            _ => Err(RpcError::MethodNotFound(method.to_string())),
+        }
+    }
+
+    // Replaces the inventory and restarts at day 0. Nothing is replaced if any item is invalid.
+    fn load(&mut self, params: &Json) -> Result<Json, RpcError> {
+        let items = params
+            .get("items")
+            .and_then(Json::as_array)
+            .ok_or_else(|| missing("items"))?;
+        let mut loaded = Vec::with_capacity(items.len());
+        for it in items {
+            let int = |key| {
+                it.get(key)
+                    .and_then(Json::as_i64)
+                    .and_then(|n| i32::try_from(n).ok())
+            };
+            let (Some(name), Some(sell_in), Some(quality)) = (
+                it.get("name").and_then(Json::as_str),
+                int("sell_in"),
+                int("quality"),
+            ) else {
+                return Err(RpcError::InvalidParams(
+                    "items need a name, sell_in and quality".into(),
+                ));
+            };
+            loaded.push(Item::new(name, sell_in, quality));
+        }
+        let rose = (self.make)(loaded);
+        let invalid = rose.validate();
+        if !invalid.is_empty() {
+            return Err(RpcError::InvalidItems(invalid));
+        }
+        self.rose = rose;
+        Ok(Json::object([("items", self.rose.items.len().into())]))
+    }
+
+    fn tick(&mut self, params: &Json) -> Result<Json, RpcError> {
+        let days = match params.get("days") {
+            None => 1,
+            Some(_) => required_int(params, "days")?,
+        };
+        if days > MAX_TICKS as usize {
+            return Err(RpcError::InvalidParams(format!(
+                "days must be at most {}",
+                MAX_TICKS
+            )));
+        }
+        for _ in 0..days {
+            self.rose.update_quality();
+        }
+        let items = (0..self.rose.items.len())
+            .map(|i| item_json(&self.rose, i))
+            .collect();
+        Ok(Json::object([
+            ("day", self.rose.day().into()),
+            ("items", Json::Array(items)),
+        ]))
+    }
+
+    // Every trial starts from a copy of the current inventory.
+    fn simulate(&self, params: &Json) -> Result<Json, RpcError> {
+        let bounded = |key, default: Option<u32>, max: u32| {
+            let n = match (params.get(key), default) {
+                (None, Some(n)) => n as usize,
+                _ => required_int(params, key)?,
+            };
+            u32::try_from(n)
+                .ok()
+                .filter(|n| (1..=max).contains(n))
+                .ok_or_else(|| RpcError::InvalidParams(format!("{} must be 1..={}", key, max)))
+        };
+        let trials = bounded("trials", None, MAX_TRIALS)?;
+        let days = bounded("days", Some(31), MAX_DAYS)?;
+        let seed = params
+            .get("seed")
+            .and_then(Json::as_i64)
+            .and_then(|n| u64::try_from(n).ok())
+            .ok_or_else(|| missing("seed"))?;
+        let demand = match params.get("demand") {
+            None => vec![DemandRule::anything()],
+            Some(rules) => rules
+                .as_array()
+                .and_then(|rules| {
+                    rules
+                        .iter()
+                        .map(|r| r.as_str().and_then(DemandRule::parse))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or_else(|| missing("demand"))?,
+        };
+        let mut mc = MonteCarlo::new(seed, trials, days, demand);
+        if let Some(prefer) = optional_str(params, "prefer")? {
+            mc.preference = Preference::parse(prefer).ok_or_else(|| missing("prefer"))?;
+        }
+        let snapshot: Vec<(String, i32, i32)> = self
+            .rose
+            .items
+            .iter()
+            .map(|it| (it.name.clone(), it.sell_in, it.quality))
+            .collect();
+        let report = mc.run(
+            || {
+                let items = snapshot
+                    .iter()
+                    .map(|(name, sell_in, quality)| Item::new(name.clone(), *sell_in, *quality))
+                    .collect();
+                (self.make)(items)
+            },
+            &Supply::default(),
+        );
+        Ok(Json::object([
+            ("trials", trials.into()),
+            ("days", days.into()),
+            ("seed", seed.into()),
+            ("revenue_cents", distribution_json(&report.revenue_cents)),
+            ("waste", distribution_json(&report.waste)),
+            ("stock_outs", distribution_json(&report.stock_outs)),
+        ]))
+    }
+}
+
+fn distribution_json(d: &Distribution) -> Json {
+    Json::object([
+        ("min", d.min.into()),
+        ("p10", d.p10.into()),
+        ("median", d.median.into()),
+        ("p90", d.p90.into()),
+        ("max", d.max.into()),
+        ("mean", d.mean.into()),
+    ])
+}
+
+fn missing(key: &str) -> RpcError {
+    RpcError::InvalidParams(format!("missing or invalid {:?}", key))
+}
+
+fn required_int(params: &Json, key: &str) -> Result<usize, RpcError> {
+    params
+        .get(key)
+        .and_then(Json::as_i64)
+        .and_then(|n| usize::try_from(n).ok())
+        .ok_or_else(|| missing(key))
+}
+
+fn optional_str<'a>(params: &'a Json, key: &str) -> Result<Option<&'a str>, RpcError> {
+    match params.get(key) {
+        None => Ok(None),
+        Some(value) => value.as_str().map(Some).ok_or_else(|| missing(key)),
+    }
+}
+
+// Replay a transcript: `-> REQUEST` lines are sent to `session`, and each answer must equal
+// the `<- RESPONSE` lines that follow, compared as JSON. Blank lines and `#` comments are
+// skipped. Returns the number of requests replayed, or the first mismatch.
+pub fn replay<F: Fn(Vec<Item>) -> GildedRose>(
+    session: &mut Session<F>,
+    transcript: &str,
+) -> Result<usize, String> {
+    let mut expected: Vec<(usize, Json)> = Vec::new();
+    let mut actual: Vec<String> = Vec::new();
+    let mut requests = 0;
+    for (n, line) in transcript.lines().enumerate() {
+        let line = line.trim();
+        if let Some(request) = line.strip_prefix("->") {
+            requests += 1;
+            actual.extend(session.handle_line(request.trim()));
+        } else if let Some(response) = line.strip_prefix("<-") {
+            let json =
+                Json::parse(response.trim()).map_err(|e| format!("line {}: {}", n + 1, e))?;
+            expected.push((n + 1, json));
+        } else if !line.is_empty() && !line.starts_with('#') {
+            return Err(format!("line {}: expected `->`, `<-` or `#`", n + 1));
+        }
+    }
+    for (i, got) in actual.iter().enumerate() {
+        let Some((line, want)) = expected.get(i) else {
+            return Err(format!("unexpected response: {}", got));
+        };
+        if Json::parse(got).as_ref() != Ok(want) {
+            return Err(format!("line {}: expected {}, got {}", line, want, got));
+        }
+    }
+    if let Some((line, want)) = expected.get(actual.len()) {
+        return Err(format!("line {}: no response for {}", line, want));
+    }
+    Ok(requests)
+}
@@ -0,0 +1,252 @@
+use crate::gilded_rose::{GildedRose, Item};
+use crate::profile::Profile;
+use crate::spec::Kind;
+use crate::stock::{Stock, StockError};
+use std::fmt::{self, Display};
+
+// Unit price in cents: a base price plus a premium per quality point.
+// Legendary items have a fixed price since their quality never moves.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Pricing {
+    pub base_cents: u64,
+    pub per_quality_cents: u64,
+    pub legendary_cents: u64,
+}
+
+impl Default for Pricing {
+    fn default() -> Self {
+        Pricing {
+            base_cents: 100,
+            per_quality_cents: 50,
+            legendary_cents: 100_000,
+        }
+    }
+}
+
+impl Pricing {
+    #[must_use]
+    pub fn price(&self, kind: &Kind<'_>, quality: i32) -> u64 {
+        if let Kind::Legendary(_) = kind {
+            return self.legendary_cents;
+        }
+        let quality = u64::try_from(quality.max(0)).unwrap_or(0);
+        self.base_cents
+            .saturating_add(self.per_quality_cents.saturating_mul(quality))
+    }
+}
+
+// Which items the shop refuses to sell.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct SalePolicy {
+    // Requirements: Sulfuras "never has to be sold"; by default it is not for sale at all.
+    pub sell_legendary: bool,
+    pub sell_expired: bool,
+    // Items below this quality are worthless (e.g. Backstage passes after the concert).
+    pub min_quality: i32,
+}
+
+impl Default for SalePolicy {
+    fn default() -> Self {
+        SalePolicy {
+            sell_legendary: false,
+            sell_expired: true,
+            min_quality: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Unsellable {
+    Legendary,
+    Expired,
+    Worthless,
+}
+
+impl SalePolicy {
+    pub fn check(&self, kind: &Kind<'_>, it: &Item) -> Result<(), Unsellable> {
+        if let Kind::Legendary(_) = kind {
+            // Legendary items never expire and their fixed quality is not a sale criterion.
+            return if self.sell_legendary {
+                Ok(())
+            } else {
+                Err(Unsellable::Legendary)
+            };
+        }
+        if !self.sell_expired && it.sell_in < 0 {
+            return Err(Unsellable::Expired);
+        }
+        if it.quality < self.min_quality {
+            return Err(Unsellable::Worthless);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ReceiptId(pub u64);
+
+impl Display for ReceiptId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "R{:06}", self.0)
+    }
+}
+
+// One completed sale. `quality` and `sell_in` are the values at the time of sale.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Sale {
+    pub receipt: ReceiptId,
+    pub day: u32,
+    pub name: String,
+    pub sell_in: i32,
+    pub quality: i32,
+    pub count: u32,
+    pub unit_price_cents: u64,
+}
+
+impl Sale {
+    pub fn total_cents(&self) -> u64 {
+        self.unit_price_cents.saturating_mul(u64::from(self.count))
+    }
+}
+
+impl Display for Sale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, day {}, {}, {}, {} x {}",
+            self.receipt,
+            self.day,
+            self.name,
+            self.quality,
+            self.count,
+            Cents(self.unit_price_cents)
+        )
+    }
+}
+
+// Formats an amount in cents as `12.34`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Cents(pub u64);
+
+impl Display for Cents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:02}", self.0 / 100, self.0 % 100)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SaleError {
+    NoSuchItem(usize),
+    Unsellable { index: usize, reason: Unsellable },
+    Stock(StockError),
+}
+
+impl Display for SaleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaleError::NoSuchItem(index) => write!(f, "no such item: {}", index),
+            SaleError::Unsellable { index, reason } => {
+                write!(f, "item {} cannot be sold: {:?}", index, reason)
+            }
+            SaleError::Stock(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<StockError> for SaleError {
+    fn from(e: StockError) -> Self {
+        SaleError::Stock(e)
+    }
+}
+
+// Records every sale with a running receipt number. Sales are appended in order, so the
+// ledger for one day is a contiguous run.
+#[derive(Debug, Clone)]
+pub struct Ledger {
+    pub pricing: Pricing,
+    pub policy: SalePolicy,
+    sales: Vec<Sale>,
+    next_receipt: u64,
+}
+
+impl Default for Ledger {
+    fn default() -> Self {
+        Ledger::new(Pricing::default(), SalePolicy::default())
+    }
+}
+
+impl Ledger {
+    pub fn new(pricing: Pricing, policy: SalePolicy) -> Ledger {
+        Ledger {
+            pricing,
+            policy,
+            sales: Vec::new(),
+            next_receipt: 1,
+        }
+    }
+
+    pub fn sales(&self) -> &[Sale] {
+        &self.sales
+    }
+
+    // The sales ledger of a single day.
+    pub fn day(&self, day: u32) -> impl Iterator<Item = &Sale> {
+        self.sales.iter().filter(move |s| s.day == day)
+    }
+
+    pub fn day_total_cents(&self, day: u32) -> u64 {
+        self.day(day).map(Sale::total_cents).sum()
+    }
+
+    // Price an item as it stands, or say why it cannot be sold.
+    pub fn quote(&self, profile: &Profile, it: &Item) -> Result<u64, Unsellable> {
+        let kind = profile.kind_of(&it.name);
+        self.policy.check(&kind, it)?;
+        Ok(self.pricing.price(&kind, it.quality))
+    }
+
+    // Sell `rose.items[index]`: the item leaves the inventory.
+    pub fn sell(&mut self, rose: &mut GildedRose, index: usize) -> Result<&Sale, SaleError> {
+        let it = rose.items.get(index).ok_or(SaleError::NoSuchItem(index))?;
+        let unit_price_cents = self
+            .quote(rose.profile(), it)
+            .map_err(|reason| SaleError::Unsellable { index, reason })?;
+        let day = rose.day();
+        let it = rose.items.remove(index);
+        Ok(self.record(day, it, 1, unit_price_cents))
+    }
+
+    // Sell `count` units of stock line `line`: the line is decremented (or removed).
+    pub fn sell_units(
+        &mut self,
+        stock: &mut Stock,
+        line: usize,
+        count: u32,
+    ) -> Result<&Sale, SaleError> {
+        let (it, _) = stock.line(line).ok_or(SaleError::NoSuchItem(line))?;
+        let unit_price_cents =
+            self.quote(stock.rose().profile(), it)
+                .map_err(|reason| SaleError::Unsellable {
+                    index: line,
+                    reason,
+                })?;
+        let day = stock.rose().day();
+        let sold = stock.take(line, count)?;
+        Ok(self.record(day, sold.item, sold.count, unit_price_cents))
+    }
+
+    fn record(&mut self, day: u32, it: Item, count: u32, unit_price_cents: u64) -> &Sale {
+        let receipt = ReceiptId(self.next_receipt);
+        self.next_receipt += 1;
+        self.sales.push(Sale {
+            receipt,
+            day,
+            name: it.name,
+            sell_in: it.sell_in,
+            quality: it.quality,
+            count,
+            unit_price_cents,
+        });
+        &self.sales[self.sales.len() - 1]
+    }
+}
@@ -0,0 +1,453 @@
+use crate::diff::{Diff, Matching};
+use crate::explain::Explanation;
+use crate::gilded_rose::{GildedRose, Item};
+use crate::json::Json;
+use crate::pattern;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+// Limits for one request; anything larger is refused before it is buffered.
+const MAX_HEADER_BYTES: usize = 8 * 1024;
+const MAX_BODY_BYTES: usize = 64 * 1024;
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Request {
+    pub method: String,
+    // Percent-decoded path without the query string, e.g. `/items/3`.
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Request {
+    // `target` is the raw request target, e.g. `/items?kind=normal`.
+    pub fn new(method: &str, target: &str, body: &str) -> Request {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let query = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+                (percent_decode(k), percent_decode(v))
+            })
+            .collect();
+        Request {
+            method: method.to_string(),
+            path: percent_decode(path),
+            query,
+            body: body.to_string(),
+        }
+    }
+
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    // Read one HTTP/1.1 request; errors are answered with the status in `Response::error`.
+    fn read(stream: &TcpStream) -> Result<Request, Response> {
+        let bad = || Response::error(400, "malformed request");
+        let mut reader = BufReader::new(stream).take(MAX_HEADER_BYTES as u64);
+        let mut line = String::new();
+        reader.read_line(&mut line).map_err(|_| bad())?;
+        let mut parts = line.split_whitespace();
+        let (Some(method), Some(target), Some(_version)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(bad());
+        };
+        let (method, target) = (method.to_string(), target.to_string());
+        let mut length = 0;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).map_err(|_| bad())? == 0 {
+                return Err(if reader.limit() == 0 {
+                    Response::error(431, "request headers too large")
+                } else {
+                    bad()
+                });
+            }
+            let header = line.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            let Some((name, value)) = header.split_once(':') else {
+                return Err(bad());
+            };
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().map_err(|_| bad())?;
+            }
+        }
+        if length > MAX_BODY_BYTES {
+            return Err(Response::error(413, "request body too large"));
+        }
+        let mut body = vec![0; length];
+        let mut reader = reader.into_inner();
+        reader.read_exact(&mut body).map_err(|_| bad())?;
+        let body = String::from_utf8(body).map_err(|_| bad())?;
+        Ok(Request::new(&method, &target, &body))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Json,
+    // The `Allow` header of a 405.
+    pub allow: Option<&'static str>,
+}
+
+impl Response {
+    pub fn json(status: u16, body: Json) -> Response {
+        Response {
+            status,
+            body,
+            allow: None,
+        }
+    }
+
+    // `{"error": "..."}` with the given status.
+    pub fn error(status: u16, message: impl Into<String>) -> Response {
+        Response::json(status, Json::object([("error", message.into().into())]))
+    }
+
+    fn method_not_allowed(allow: &'static str) -> Response {
+        Response {
+            allow: Some(allow),
+            ..Response::error(405, "method not allowed")
+        }
+    }
+
+    fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        let body = self.body.to_string();
+        write!(
+            out,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.status,
+            reason(self.status),
+            body.len()
+        )?;
+        if let Some(allow) = self.allow {
+            write!(out, "Allow: {}\r\n", allow)?;
+        }
+        write!(out, "\r\n{}", body)?;
+        out.flush()
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        431 => "Request Header Fields Too Large",
+        _ => "",
+    }
+}
+
+// The inventory behind the HTTP API, with a listing of every past day for `/report/{day}`.
+//
+// | Method | Path                  | Answer                                           |
+// |--------|-----------------------|--------------------------------------------------|
+// | GET    | `/items`              | all items; filter with `?name=GLOB` / `?kind=K`  |
+// | POST   | `/items`              | add `{"name", "sell_in", "quality"}`; 201        |
+// | GET    | `/items/{i}`          | one item                                         |
+// | DELETE | `/items/{i}`          | remove it; later indexes shift down              |
+// | GET    | `/items/{i}/explain`  | `Explanation` of the next update                 |
+// | POST   | `/tick`               | run `update_quality` once                        |
+// | GET    | `/report/{day}`       | listing and totals as of that day                |
+// | GET    | `/diff/{day}`         | `Diff` from that day to today, or to `?to=DAY`;  |
+// |        |                       | `?match=position` pairs items by index           |
+pub struct Service {
+    rose: GildedRose,
+    // `history[d]` is the listing on day `first_day + d`, for every day before today.
+    history: Vec<Vec<Item>>,
+    first_day: u32,
+}
+
+impl Service {
+    pub fn new(rose: GildedRose) -> Service {
+        Service {
+            first_day: rose.day(),
+            rose,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn rose(&self) -> &GildedRose {
+        &self.rose
+    }
+
+    pub fn handle(&mut self, req: &Request) -> Response {
+        let segments: Vec<&str> = req.path.trim_matches('/').split('/').collect();
+        match (segments.as_slice(), req.method.as_str()) {
+            (["items"], "GET") => self.list(req),
+            (["items"], "POST") => self.add(&req.body),
+            (["items"], _) => Response::method_not_allowed("GET, POST"),
+            (["items", i], "GET") => self.with_index(i, |s, i| item_json(s.rose(), i)),
+            (["items", i], "DELETE") => match self.index(i) {
+                Some(i) => Response::json(200, self.remove(i)),
+                None => Response::error(404, "no such item"),
+            },
+            (["items", _], _) => Response::method_not_allowed("GET, DELETE"),
+            (["items", i, "explain"], "GET") => self.with_index(i, |s, i| {
+                Explanation::of(s.rose(), i).map_or(Json::Null, |e| e.to_json())
+            }),
+            (["items", _, "explain"], _) => Response::method_not_allowed("GET"),
+            (["tick"], "POST") => self.tick(),
+            (["tick"], _) => Response::method_not_allowed("POST"),
+            (["report", day], "GET") => match day.parse().ok().and_then(|d| self.report(d)) {
+                Some(report) => Response::json(200, report),
+                None => Response::error(404, "no report for that day"),
+            },
+            (["report", _], _) => Response::method_not_allowed("GET"),
+            (["diff", day], "GET") => self.diff(day, req),
+            (["diff", _], _) => Response::method_not_allowed("GET"),
+            _ => Response::error(404, "not found"),
+        }
+    }
+
+    fn index(&self, segment: &str) -> Option<usize> {
+        segment.parse().ok().filter(|i| *i < self.rose.items.len())
+    }
+
+    fn with_index(&self, segment: &str, f: impl FnOnce(&Service, usize) -> Json) -> Response {
+        match self.index(segment) {
+            Some(i) => Response::json(200, f(self, i)),
+            None => Response::error(404, "no such item"),
+        }
+    }
+
+    fn list(&self, req: &Request) -> Response {
+        Response::json(
+            200,
+            query_json(&self.rose, req.param("name"), req.param("kind")),
+        )
+    }
+
+    fn add(&mut self, body: &str) -> Response {
+        let json = match Json::parse(body) {
+            Ok(json) => json,
+            Err(e) => return Response::error(400, e.to_string()),
+        };
+        let int = |key| {
+            json.get(key)
+                .and_then(Json::as_i64)
+                .and_then(|n| i32::try_from(n).ok())
+        };
+        let (Some(name), Some(sell_in), Some(quality)) = (
+            json.get("name").and_then(Json::as_str),
+            int("sell_in"),
+            int("quality"),
+        ) else {
+            return Response::error(
+                400,
+                "expected {\"name\": string, \"sell_in\": int, \"quality\": int}",
+            );
+        };
+        let item = Item::new(name, sell_in, quality);
+        if let Err(reason) = self.rose.validate_item(&item) {
+            return Response::error(422, reason);
+        }
+        self.rose.items.push(item);
+        Response::json(201, item_json(&self.rose, self.rose.items.len() - 1))
+    }
+
+    fn remove(&mut self, index: usize) -> Json {
+        let json = item_json(&self.rose, index);
+        self.rose.items.remove(index);
+        json
+    }
+
+    fn tick(&mut self) -> Response {
+        self.history.push(snapshot(&self.rose.items));
+        self.rose.update_quality();
+        Response::json(200, self.report(self.rose.day()).unwrap_or(Json::Null))
+    }
+
+    fn diff(&self, from: &str, req: &Request) -> Response {
+        let to = match req.param("to") {
+            Some(to) => to.parse().ok(),
+            None => Some(self.rose.day()),
+        };
+        let matching = match req.param("match") {
+            Some(m) => Matching::parse(m),
+            None => Some(Matching::Name),
+        };
+        let (Some(to), Some(matching)) = (to, matching) else {
+            return Response::error(400, "expected ?to=DAY and ?match=name|position");
+        };
+        let from: Option<u32> = from.parse().ok();
+        let (Some(from), Some(after)) = (from, self.listing(to)) else {
+            return Response::error(404, "no listing for that day");
+        };
+        let Some(before) = self.listing(from) else {
+            return Response::error(404, "no listing for that day");
+        };
+        let mut fields = vec![
+            ("from".to_string(), from.into()),
+            ("to".to_string(), to.into()),
+        ];
+        if let Json::Object(rest) = Diff::between(before, after, matching).to_json() {
+            fields.extend(rest);
+        }
+        Response::json(200, Json::Object(fields))
+    }
+
+    // The items on `day`; `None` for a day before the service started or after today.
+    fn listing(&self, day: u32) -> Option<&[Item]> {
+        match day.checked_sub(self.first_day)? as usize {
+            d if d < self.history.len() => Some(&self.history[d]),
+            d if d == self.history.len() => Some(&self.rose.items),
+            _ => None,
+        }
+    }
+
+    // `{"day", "items": [...], "totals": {"items", "quality", "expired"}}`; `None` for a day
+    // before the service started or after today.
+    pub fn report(&self, day: u32) -> Option<Json> {
+        let items = self.listing(day)?;
+        let quality: i64 = items.iter().map(|it| i64::from(it.quality)).sum();
+        let expired = items.iter().filter(|it| it.sell_in.is_negative()).count();
+        Some(Json::object([
+            ("day", day.into()),
+            (
+                "items",
+                Json::Array(items.iter().map(|it| plain_json(&self.rose, it)).collect()),
+            ),
+            (
+                "totals",
+                Json::object([
+                    ("items", items.len().into()),
+                    ("quality", Json::Int(quality)),
+                    ("expired", expired.into()),
+                ]),
+            ),
+        ]))
+    }
+}
+
+fn snapshot(items: &[Item]) -> Vec<Item> {
+    items
+        .iter()
+        .map(|it| Item::new(it.name.clone(), it.sell_in, it.quality))
+        .collect()
+}
+
+// Items whose name matches the glob `name` and whose kind label is `kind`, as `item_json`.
+pub(crate) fn query_json(rose: &GildedRose, name: Option<&str>, kind: Option<&str>) -> Json {
+    let items = (0..rose.items.len())
+        .filter(|&i| {
+            let it = &rose.items[i];
+            name.is_none_or(|p| pattern::matches(p, &it.name))
+                && kind.is_none_or(|k| rose.kind_label(&it.name) == k)
+        })
+        .map(|i| item_json(rose, i))
+        .collect();
+    Json::Array(items)
+}
+
+// `{"name", "sell_in", "quality", "kind"}`
+pub(crate) fn plain_json(rose: &GildedRose, it: &Item) -> Json {
+    Json::object([
+        ("name", it.name.as_str().into()),
+        ("sell_in", it.sell_in.into()),
+        ("quality", it.quality.into()),
+        ("kind", rose.kind_label(&it.name).into()),
+    ])
+}
+
+// `plain_json` with the item's current index first.
+pub(crate) fn item_json(rose: &GildedRose, index: usize) -> Json {
+    let mut fields = vec![("index".to_string(), index.into())];
+    if let Json::Object(rest) = plain_json(rose, &rose.items[index]) {
+        fields.extend(rest);
+    }
+    Json::Object(fields)
+}
+
+// `%XX` escapes and `+` for space; malformed escapes are kept as they are.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b'+', _) => {
+                out.push(b' ');
+                i += 1;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// One connection per request, answered in turn on the calling thread.
+pub struct Server {
+    listener: TcpListener,
+    service: Service,
+}
+
+impl Server {
+    pub fn bind(addr: impl ToSocketAddrs, rose: GildedRose) -> io::Result<Server> {
+        Ok(Server {
+            listener: TcpListener::bind(addr)?,
+            service: Service::new(rose),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn service(&self) -> &Service {
+        &self.service
+    }
+
+    // Accept and answer one connection. Only accepting can fail: a client that hangs up or
+    // times out just does not get its answer.
+    pub fn handle_one(&mut self) -> io::Result<()> {
+        let (mut stream, _) = self.listener.accept()?;
+        let _ = stream.set_read_timeout(Some(CLIENT_TIMEOUT));
+        let _ = stream.set_write_timeout(Some(CLIENT_TIMEOUT));
+        match Request::read(&stream) {
+            Ok(req) => {
+                let _ = self.service.handle(&req).write_to(&mut stream);
+            }
+            Err(response) => {
+                let _ = response.write_to(&mut stream);
+                // Closing with unread input would reset the connection before the client
+                // reads the answer, so drain a bounded amount of what it is still sending.
+                let _ = stream.shutdown(Shutdown::Write);
+                let _ = io::copy(&mut (&stream).take(MAX_BODY_BYTES as u64), &mut io::sink());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn serve(&mut self) -> io::Result<()> {
+        loop {
+            self.handle_one()?;
+        }
+    }
+}
@@ -1,3 +1,6 @@
+use crate::appreciating::Appreciation;
+use crate::decay::DecayCurve;
+
 pub const QUALITY_MIN: i32 = 0;
 pub const QUALITY_MAX: i32 = 50;
 
@@ -23,61 +26,71 @@ pub fn dec_to_floor(q: i32, n: i32) -> i32 {
     }
 }
 
-#[cfg(debug_assertions)]
-pub fn assert_preconditions(kind: &Kind, q: i32) {
-    // Legendary (Sulfuras): quality must be exactly 80.
-    if matches!(kind, Kind::Legendary) {
-        debug_assert!(
-            q == 80,
-            "Legendary (Sulfuras) must have quality 80, got {}",
-            q
-        );
-        return;
+// Runtime form of the preconditions, for validating input instead of asserting on it.
+pub fn check_preconditions(kind: &Kind, q: i32) -> Result<(), String> {
+    // Legendary: quality must be exactly the registered one (80 for Sulfuras).
+    if let Kind::Legendary(expected) = kind {
+        if q != *expected {
+            return Err(format!(
+                "Legendary must have quality {}, got {}",
+                expected, q
+            ));
+        }
+        return Ok(());
     }
     // Non-legendary items: quality is expected to start within [0, 50].
-    if !matches!(kind, Kind::Legendary) {
-        debug_assert!(
-            (QUALITY_MIN..=QUALITY_MAX).contains(&q),
-            "quality out of range: {}",
-            q
-        );
+    if !(QUALITY_MIN..=QUALITY_MAX).contains(&q) {
+        return Err(format!("quality out of range: {}", q));
+    }
+    Ok(())
+}
+
+#[cfg(debug_assertions)]
+pub fn assert_preconditions(kind: &Kind, q: i32) {
+    if let Err(e) = check_preconditions(kind, q) {
+        panic!("{}", e);
     }
 }
 
+// Borrows registry data from the `Profile` that classified the item.
 #[derive(Debug, Clone, Eq, PartialEq)]
-pub enum Kind {
-    AgedBrie,
+pub enum Kind<'p> {
+    // Aged Brie and other goods from the appreciating registry.
+    Appreciating(Appreciation),
     BackstagePass,
-    Legendary,
+    // Carries the fixed quality from the legendary registry.
+    Legendary(i32),
+    // Goods from the perishables registry, decaying along their own curve.
+    Perishable(&'p DecayCurve),
     Normal,
 }
 
-const NAME_BRIE: &str = "Aged Brie";
-const PREFIX_BACKSTAGE: &str = "Backstage passes";
-const NAME_SULFURAS: &str = "Sulfuras, Hand of Ragnaros";
+impl Kind<'_> {
+    // Short lowercase name for reports and APIs.
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            Kind::Appreciating(_) => "appreciating",
+            Kind::BackstagePass => "backstage",
+            Kind::Legendary(_) => "legendary",
+            Kind::Perishable(_) => "perishable",
+            Kind::Normal => "normal",
+        }
+    }
+}
+
+pub const PREFIX_BACKSTAGE: &str = "Backstage passes";
 
-impl From<&str> for Kind {
+// Built-in name rules. Registered kinds (legendary, appreciating, perishable) come from `Profile::classify`.
+impl From<&str> for Kind<'_> {
     #[inline]
     fn from(name: &str) -> Self {
         match name {
-            NAME_BRIE => Kind::AgedBrie,
-            NAME_SULFURAS => Kind::Legendary,
             s if s.starts_with(PREFIX_BACKSTAGE) => Kind::BackstagePass,
             _ => Kind::Normal,
         }
     }
 }
 
-// Detect "Conjured " as an item property (not a separate type).
-const PREFIX_CONJURED: &str = "Conjured ";
-
-#[must_use]
-#[inline]
-pub fn split_conjured(name: &str) -> (bool, &str) {
-    // Assumes valid inputs: if it starts with "Conjured ", there is a non-empty base name after it.
-    if let Some(rest) = name.strip_prefix(PREFIX_CONJURED) {
-        (true, rest)
-    } else {
-        (false, name)
-    }
-}
+// Detect "Conjured " as an item property (not a separate type); see `modifier.rs`.
+pub const PREFIX_CONJURED: &str = "Conjured ";
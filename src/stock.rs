@@ -0,0 +1,206 @@
+use crate::behavior::ItemBehavior;
+use crate::event::{EventError, Refund};
+use crate::gilded_rose::{GildedRose, Item};
+use crate::profile::Profile;
+use std::fmt::{self, Display};
+
+// One inventory line: `count` identical units of `item`.
+pub struct StockLine {
+    pub item: Item,
+    pub count: u32,
+}
+
+impl StockLine {
+    pub fn new(item: Item, count: u32) -> StockLine {
+        StockLine { item, count }
+    }
+}
+
+impl Display for StockLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {}", self.item, self.count)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum StockError {
+    NoSuchLine(usize),
+    // `requested` units cannot be split off or taken from a line holding `available`.
+    InvalidCount {
+        line: usize,
+        available: u32,
+        requested: u32,
+    },
+}
+
+impl Display for StockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StockError::NoSuchLine(line) => write!(f, "no such stock line: {}", line),
+            StockError::InvalidCount {
+                line,
+                available,
+                requested,
+            } => write!(
+                f,
+                "cannot move {} of {} units on stock line {}",
+                requested, available, line
+            ),
+        }
+    }
+}
+
+// Quantity-aware inventory. Each line's `Item` lives in an inner `GildedRose`, so a line ages
+// once per day regardless of its count, under the same profile and events as single items.
+// `counts[i]` is the unit count of `rose.items[i]`.
+pub struct Stock {
+    rose: GildedRose,
+    counts: Vec<u32>,
+}
+
+impl Stock {
+    pub fn new(lines: Vec<StockLine>) -> Stock {
+        Self::with_profile(lines, Profile::default())
+    }
+
+    pub fn with_profile(lines: Vec<StockLine>, profile: Profile) -> Stock {
+        let (items, counts) = lines.into_iter().map(|l| (l.item, l.count)).unzip();
+        let mut stock = Stock {
+            rose: GildedRose::with_profile(items, profile),
+            counts,
+        };
+        stock.merge();
+        stock
+    }
+
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = (&Item, u32)> {
+        self.rose.items.iter().zip(self.counts.iter().copied())
+    }
+
+    pub fn line(&self, line: usize) -> Option<(&Item, u32)> {
+        Some((self.rose.items.get(line)?, *self.counts.get(line)?))
+    }
+
+    pub fn total_units(&self) -> u64 {
+        self.counts.iter().map(|&c| u64::from(c)).sum()
+    }
+
+    // Read-only access to the inner inventory (day, profile, events); changes go through the
+    // forwarding methods below, so the line layout is never exposed mutably.
+    pub fn rose(&self) -> &GildedRose {
+        &self.rose
+    }
+
+    // See `GildedRose::add_behavior`.
+    pub fn add_behavior(&mut self, behavior: Box<dyn ItemBehavior>) {
+        self.rose.add_behavior(behavior);
+    }
+
+    // See `GildedRose::schedule_event`. Lines that now match are merged at the next update.
+    pub fn schedule_event(&mut self, event: &str, days_left: i32) {
+        self.rose.schedule_event(event, days_left);
+    }
+
+    // See `GildedRose::postpone_event`.
+    pub fn postpone_event(&mut self, event: &str, days: u32) -> Result<(), EventError> {
+        self.rose.postpone_event(event, days)
+    }
+
+    // See `GildedRose::cancel_event`. A refund's `index` is its line, covering all of the line's
+    // units; lines are not merged until the next update, so the indexes stay valid until then.
+    pub fn cancel_event(&mut self, event: &str) -> Result<Vec<Refund>, EventError> {
+        self.rose.cancel_event(event)
+    }
+
+    // Add a line; it is merged into an identical existing line if there is one.
+    pub fn push(&mut self, line: StockLine) {
+        self.rose.items.push(line.item);
+        self.counts.push(line.count);
+        self.merge();
+    }
+
+    // Age every line once, then merge lines that became identical.
+    pub fn update_quality(&mut self) {
+        self.rose.update_quality();
+        self.merge();
+    }
+
+    // Move `count` units of `line` onto a new line at the end (e.g. damaged units) and return
+    // its index. At least one unit must stay behind. The new line is not merged until the next
+    // `merge`, so the caller can change it first.
+    pub fn split(&mut self, line: usize, count: u32) -> Result<usize, StockError> {
+        let available = self.checked_count(line, count, 1)?;
+        let it = &self.rose.items[line];
+        let copy = Item::new(it.name.clone(), it.sell_in, it.quality);
+        self.counts[line] = available - count;
+        self.rose.items.push(copy);
+        self.counts.push(count);
+        Ok(self.counts.len() - 1)
+    }
+
+    // Mutable access to a line's item, e.g. to lower the quality of a damaged split.
+    pub fn item_mut(&mut self, line: usize) -> Result<&mut Item, StockError> {
+        self.rose
+            .items
+            .get_mut(line)
+            .ok_or(StockError::NoSuchLine(line))
+    }
+
+    // Remove `count` units of `line` (e.g. sold); the line disappears when it runs out.
+    pub fn take(&mut self, line: usize, count: u32) -> Result<StockLine, StockError> {
+        let available = self.checked_count(line, count, 0)?;
+        if available == count {
+            self.counts.remove(line);
+            return Ok(StockLine::new(self.rose.items.remove(line), count));
+        }
+        self.counts[line] = available - count;
+        let it = &self.rose.items[line];
+        Ok(StockLine::new(
+            Item::new(it.name.clone(), it.sell_in, it.quality),
+            count,
+        ))
+    }
+
+    // Fold lines with the same name, sell_in and quality into the first of them, keeping order.
+    // Lines that hold no units are dropped.
+    pub fn merge(&mut self) {
+        let items = std::mem::take(&mut self.rose.items);
+        let counts = std::mem::take(&mut self.counts);
+        for (it, count) in items.into_iter().zip(counts) {
+            if count == 0 {
+                continue;
+            }
+            let same = self.rose.items.iter().position(|kept| {
+                kept.name == it.name && kept.sell_in == it.sell_in && kept.quality == it.quality
+            });
+            match same {
+                Some(i) => self.counts[i] = self.counts[i].saturating_add(count),
+                None => {
+                    self.rose.items.push(it);
+                    self.counts.push(count);
+                }
+            }
+        }
+    }
+
+    // `count` must be in `1..=available - keep`.
+    fn checked_count(&self, line: usize, count: u32, keep: u32) -> Result<u32, StockError> {
+        let available = *self.counts.get(line).ok_or(StockError::NoSuchLine(line))?;
+        if count == 0 || count.saturating_add(keep) > available {
+            return Err(StockError::InvalidCount {
+                line,
+                available,
+                requested: count,
+            });
+        }
+        Ok(available)
+    }
+}
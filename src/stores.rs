@@ -0,0 +1,320 @@
+use crate::gilded_rose::{GildedRose, Item};
+use std::fmt::{self, Display};
+
+// The name of the summed row in `ConsolidatedReport`; no store may take it.
+pub const STORE_TOTAL: &str = "all";
+
+// One item moved between stores, with the values it had when it left.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Transfer {
+    pub day: u32,
+    pub from: String,
+    pub to: String,
+    pub name: String,
+    pub sell_in: i32,
+    pub quality: i32,
+}
+
+impl Display for Transfer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "day {}, {} -> {}: {}, {}, {}",
+            self.day, self.from, self.to, self.name, self.sell_in, self.quality
+        )
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum StoreError {
+    DuplicateStore(String),
+    // `STORE_TOTAL`, which would be mistaken for the total row of a report.
+    ReservedName(String),
+    UnknownStore(String),
+    // A store joining the chain must be on the chain's business day.
+    DayMismatch {
+        store: String,
+        day: u32,
+        expected: u32,
+    },
+    NoSuchItem {
+        store: String,
+        index: usize,
+    },
+    SameStore(String),
+    // The receiving store's rules reject the item (e.g. another legendary quality).
+    Rejected {
+        store: String,
+        reason: String,
+    },
+}
+
+impl Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::DuplicateStore(store) => write!(f, "store already exists: {}", store),
+            StoreError::ReservedName(store) => {
+                write!(f, "store name is reserved for the total: {}", store)
+            }
+            StoreError::UnknownStore(store) => write!(f, "no such store: {}", store),
+            StoreError::DayMismatch {
+                store,
+                day,
+                expected,
+            } => write!(f, "store {} is on day {}, not day {}", store, day, expected),
+            StoreError::NoSuchItem { store, index } => {
+                write!(f, "no item {} in store {}", index, store)
+            }
+            StoreError::SameStore(store) => write!(f, "cannot transfer within store {}", store),
+            StoreError::Rejected { store, reason } => {
+                write!(f, "store {} rejects: {}", store, reason)
+            }
+        }
+    }
+}
+
+// Totals for one store, or for all of them.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct StoreReport {
+    pub store: String,
+    pub items: usize,
+    pub quality: i64,
+    pub expired: usize,
+    // Items per `GildedRose::kind_label`, in label order.
+    pub by_kind: Vec<(&'static str, usize)>,
+    // Transfers recorded so far, counted at the receiving and the sending store.
+    pub transfers_in: usize,
+    pub transfers_out: usize,
+}
+
+impl StoreReport {
+    fn of(store: &str, rose: &GildedRose, transfers: &[Transfer]) -> StoreReport {
+        let mut by_kind: Vec<(&'static str, usize)> = Vec::new();
+        for it in &rose.items {
+            let label = rose.kind_label(&it.name);
+            match by_kind.binary_search_by_key(&label, |(l, _)| l) {
+                Ok(i) => by_kind[i].1 += 1,
+                Err(i) => by_kind.insert(i, (label, 1)),
+            }
+        }
+        StoreReport {
+            store: store.to_string(),
+            items: rose.items.len(),
+            quality: rose.items.iter().map(|it| i64::from(it.quality)).sum(),
+            expired: rose
+                .items
+                .iter()
+                .filter(|it| it.sell_in.is_negative())
+                .count(),
+            by_kind,
+            transfers_in: transfers.iter().filter(|t| t.to == store).count(),
+            transfers_out: transfers.iter().filter(|t| t.from == store).count(),
+        }
+    }
+
+    // Add another store's totals; transfers within the sum cancel out.
+    fn absorb(&mut self, other: &StoreReport) {
+        self.items += other.items;
+        self.quality += other.quality;
+        self.expired += other.expired;
+        for &(label, n) in &other.by_kind {
+            match self.by_kind.binary_search_by_key(&label, |(l, _)| l) {
+                Ok(i) => self.by_kind[i].1 += n,
+                Err(i) => self.by_kind.insert(i, (label, n)),
+            }
+        }
+    }
+}
+
+impl Display for StoreReport {
+    // `store, items, quality, expired, in, out, kinds`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, {}, {}, {}, {}, {},",
+            self.store,
+            self.items,
+            self.quality,
+            self.expired,
+            self.transfers_in,
+            self.transfers_out
+        )?;
+        for (label, n) in &self.by_kind {
+            write!(f, " {} {}", label, n)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ConsolidatedReport {
+    pub day: u32,
+    pub stores: Vec<StoreReport>,
+    // Sum over all stores, named `STORE_TOTAL`; transfers are internal, so both counts are 0.
+    pub total: StoreReport,
+}
+
+impl Display for ConsolidatedReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "day {}", self.day)?;
+        writeln!(f, "store, items, quality, expired, in, out, kinds")?;
+        for store in &self.stores {
+            writeln!(f, "{}", store)?;
+        }
+        write!(f, "{}", self.total)
+    }
+}
+
+// Several shops, each with its own `GildedRose` (and so its own profile, events and custom
+// kinds), kept on one business day.
+#[derive(Default)]
+pub struct Stores {
+    stores: Vec<(String, GildedRose)>,
+    day: u32,
+    transfers: Vec<Transfer>,
+}
+
+impl Stores {
+    pub fn new() -> Stores {
+        Stores::default()
+    }
+
+    // The first store sets the business day; later ones must be on it. `STORE_TOTAL` is not a
+    // valid name.
+    pub fn add_store(
+        &mut self,
+        name: impl Into<String>,
+        rose: GildedRose,
+    ) -> Result<(), StoreError> {
+        let name = name.into();
+        if name == STORE_TOTAL {
+            return Err(StoreError::ReservedName(name));
+        }
+        if self.get(&name).is_some() {
+            return Err(StoreError::DuplicateStore(name));
+        }
+        if self.stores.is_empty() {
+            self.day = rose.day();
+        } else if rose.day() != self.day {
+            return Err(StoreError::DayMismatch {
+                store: name,
+                day: rose.day(),
+                expected: self.day,
+            });
+        }
+        self.stores.push((name, rose));
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&GildedRose> {
+        self.stores.iter().find(|(n, _)| n == name).map(|(_, r)| r)
+    }
+
+    // For stocking and events; advance days with `tick` only, or the store drifts off the
+    // business day.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut GildedRose> {
+        self.stores
+            .iter_mut()
+            .find(|(n, _)| n == name)
+            .map(|(_, r)| r)
+    }
+
+    // Store names in the order they were added.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.stores.iter().map(|(n, _)| n.as_str())
+    }
+
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+
+    pub fn transfers(&self) -> &[Transfer] {
+        &self.transfers
+    }
+
+    // One business day for every store.
+    pub fn tick(&mut self) {
+        for (_, rose) in &mut self.stores {
+            rose.update_quality();
+        }
+        self.day = self.day.saturating_add(1);
+    }
+
+    // Move `from`'s item `index` to the end of `to`, keeping `sell_in` and quality. The item
+    // is checked against the receiving store's rules first; on any error nothing moves.
+    pub fn transfer(
+        &mut self,
+        from: &str,
+        index: usize,
+        to: &str,
+    ) -> Result<&Transfer, StoreError> {
+        if from == to {
+            return Err(StoreError::SameStore(from.to_string()));
+        }
+        let src = self.position(from)?;
+        let dst = self.position(to)?;
+        let Some(it) = self.stores[src].1.items.get(index) else {
+            return Err(StoreError::NoSuchItem {
+                store: from.to_string(),
+                index,
+            });
+        };
+        let moved = Item::new(it.name.clone(), it.sell_in, it.quality);
+        if let Err(reason) = self.stores[dst].1.validate_item(&moved) {
+            return Err(StoreError::Rejected {
+                store: to.to_string(),
+                reason,
+            });
+        }
+        self.stores[src].1.items.remove(index);
+        self.transfers.push(Transfer {
+            day: self.day,
+            from: from.to_string(),
+            to: to.to_string(),
+            name: moved.name.clone(),
+            sell_in: moved.sell_in,
+            quality: moved.quality,
+        });
+        self.stores[dst].1.items.push(moved);
+        Ok(&self.transfers[self.transfers.len() - 1])
+    }
+
+    pub fn store_report(&self, name: &str) -> Result<StoreReport, StoreError> {
+        let rose = self
+            .get(name)
+            .ok_or_else(|| StoreError::UnknownStore(name.to_string()))?;
+        Ok(StoreReport::of(name, rose, &self.transfers))
+    }
+
+    pub fn report(&self) -> ConsolidatedReport {
+        let stores: Vec<StoreReport> = self
+            .stores
+            .iter()
+            .map(|(name, rose)| StoreReport::of(name, rose, &self.transfers))
+            .collect();
+        let mut total = StoreReport {
+            store: STORE_TOTAL.to_string(),
+            items: 0,
+            quality: 0,
+            expired: 0,
+            by_kind: Vec::new(),
+            transfers_in: 0,
+            transfers_out: 0,
+        };
+        for store in &stores {
+            total.absorb(store);
+        }
+        ConsolidatedReport {
+            day: self.day,
+            stores,
+            total,
+        }
+    }
+
+    fn position(&self, name: &str) -> Result<usize, StoreError> {
+        self.stores
+            .iter()
+            .position(|(n, _)| n == name)
+            .ok_or_else(|| StoreError::UnknownStore(name.to_string()))
+    }
+}
@@ -0,0 +1,253 @@
+use crate::gilded_rose::{GildedRose, Item};
+use crate::pattern::Selector;
+use std::fmt::{self, Display};
+
+// What a supplier ships: `count` units of a fresh item.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ItemSpec {
+    pub name: String,
+    pub sell_in: i32,
+    pub quality: i32,
+    pub count: u32,
+}
+
+impl ItemSpec {
+    pub fn new(name: impl Into<String>, sell_in: i32, quality: i32, count: u32) -> ItemSpec {
+        ItemSpec {
+            name: name.into(),
+            sell_in,
+            quality,
+            count,
+        }
+    }
+
+    // Parse `NAME=SELL_IN,QUALITY[,COUNT]`; the count defaults to 1.
+    pub fn parse(s: &str) -> Option<ItemSpec> {
+        let (name, values) = s.rsplit_once('=')?;
+        let mut values = values.split(',');
+        let sell_in = values.next()?.parse().ok()?;
+        let quality = values.next()?.parse().ok()?;
+        let count = match values.next() {
+            Some(n) => n.parse().ok()?,
+            None => 1,
+        };
+        if name.is_empty() || values.next().is_some() {
+            return None;
+        }
+        Some(ItemSpec::new(name, sell_in, quality, count))
+    }
+
+    fn items(&self) -> impl Iterator<Item = Item> + '_ {
+        (0..self.count).map(|_| Item::new(self.name.clone(), self.sell_in, self.quality))
+    }
+}
+
+impl Display for ItemSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, {}, {} x {}",
+            self.name, self.sell_in, self.quality, self.count
+        )
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Supplier {
+    pub name: String,
+    // Days between placing an order and its delivery.
+    pub lead_time: u32,
+}
+
+impl Supplier {
+    // Parse `NAME=LEAD_TIME`.
+    pub fn parse(s: &str) -> Option<Supplier> {
+        let (name, lead_time) = s.rsplit_once('=')?;
+        if name.is_empty() {
+            return None;
+        }
+        Some(Supplier {
+            name: name.to_string(),
+            lead_time: lead_time.parse().ok()?,
+        })
+    }
+}
+
+// Order `order` from `supplier` when fewer than `threshold` items whose name matches
+// `pattern` are in stock. Only one order per rule is outstanding at a time.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ReorderRule {
+    // The items counted against `threshold`; by default the ordered name itself.
+    pub selector: Selector,
+    pub threshold: usize,
+    pub order: ItemSpec,
+    pub supplier: String,
+}
+
+impl ReorderRule {
+    // Parse `NAME=SELL_IN,QUALITY[,COUNT]<THRESHOLD[/SELECTOR]@SUPPLIER`: reorder `NAME` when
+    // fewer than `THRESHOLD` items match `SELECTOR` (a name glob or `kind:LABEL`, see
+    // `Selector`), by default `NAME` itself.
+    pub fn parse(s: &str) -> Option<ReorderRule> {
+        let (rest, supplier) = s.rsplit_once('@')?;
+        let (order, threshold) = rest.rsplit_once('<')?;
+        let order = ItemSpec::parse(order)?;
+        if supplier.is_empty() {
+            return None;
+        }
+        let (threshold, selector) = match threshold.split_once('/') {
+            Some((threshold, selector)) => (threshold, Selector::parse(selector)?),
+            None => (threshold, Selector::Name(order.name.clone())),
+        };
+        Some(ReorderRule {
+            selector,
+            threshold: threshold.parse().ok()?,
+            order,
+            supplier: supplier.to_string(),
+        })
+    }
+}
+
+// Goods arriving at the start of `day`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Delivery {
+    pub day: u32,
+    pub supplier: Option<String>,
+    pub items: Vec<ItemSpec>,
+    // Index of the reorder rule that placed this order, if any.
+    pub reorder: Option<usize>,
+}
+
+impl Delivery {
+    pub fn new(day: u32, items: Vec<ItemSpec>) -> Delivery {
+        Delivery {
+            day,
+            supplier: None,
+            items,
+            reorder: None,
+        }
+    }
+
+    // Parse `NAME=SELL_IN,QUALITY[,COUNT]@DAY`.
+    pub fn parse(s: &str) -> Option<Delivery> {
+        let (spec, day) = s.rsplit_once('@')?;
+        Some(Delivery::new(
+            day.parse().ok()?,
+            vec![ItemSpec::parse(spec)?],
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SupplyError {
+    UnknownSupplier(String),
+}
+
+impl Display for SupplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SupplyError::UnknownSupplier(name) => write!(f, "unknown supplier: {}", name),
+        }
+    }
+}
+
+// What happened at the start of a day.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct SupplyReport {
+    // Deliveries as stocked: only the specs that were accepted.
+    pub received: Vec<Delivery>,
+    pub ordered: Vec<Delivery>,
+    // Specs `GildedRose::validate_item` turned away, with the reason; none of them was stocked.
+    pub rejected: Vec<(ItemSpec, String)>,
+}
+
+// Scheduled deliveries, suppliers and the reorder policy for one `GildedRose`.
+#[derive(Debug, Clone, Default)]
+pub struct Supply {
+    suppliers: Vec<Supplier>,
+    rules: Vec<ReorderRule>,
+    pending: Vec<Delivery>,
+}
+
+impl Supply {
+    pub fn pending(&self) -> &[Delivery] {
+        &self.pending
+    }
+
+    pub fn add_supplier(&mut self, supplier: Supplier) {
+        self.suppliers.retain(|s| s.name != supplier.name);
+        self.suppliers.push(supplier);
+    }
+
+    pub fn add_rule(&mut self, rule: ReorderRule) -> Result<(), SupplyError> {
+        if !self.suppliers.iter().any(|s| s.name == rule.supplier) {
+            return Err(SupplyError::UnknownSupplier(rule.supplier));
+        }
+        self.rules.push(rule);
+        Ok(())
+    }
+
+    pub fn schedule(&mut self, delivery: Delivery) {
+        self.pending.push(delivery);
+    }
+
+    // Call at the start of each day, before selling or printing: receives everything due by
+    // `rose.day()`, then places orders for rules whose stock is below threshold. An order
+    // arrives at the start of `day + lead_time`, so a zero lead time is received right away.
+    pub fn start_day(&mut self, rose: &mut GildedRose) -> SupplyReport {
+        let mut report = SupplyReport::default();
+        self.receive(rose, &mut report);
+        let day = rose.day();
+        for (index, rule) in self.rules.iter().enumerate() {
+            let outstanding = self.pending.iter().any(|d| d.reorder == Some(index));
+            let in_stock = rose
+                .items
+                .iter()
+                .filter(|it| rule.selector.matches(rose, &it.name))
+                .count();
+            if outstanding || in_stock >= rule.threshold {
+                continue;
+            }
+            // Suppliers are checked in `add_rule`.
+            let lead_time = self
+                .suppliers
+                .iter()
+                .find(|s| s.name == rule.supplier)
+                .map_or(0, |s| s.lead_time);
+            let order = Delivery {
+                day: day.saturating_add(lead_time),
+                supplier: Some(rule.supplier.clone()),
+                items: vec![rule.order.clone()],
+                reorder: Some(index),
+            };
+            report.ordered.push(order.clone());
+            self.pending.push(order);
+        }
+        self.receive(rose, &mut report);
+        report
+    }
+
+    fn receive(&mut self, rose: &mut GildedRose, report: &mut SupplyReport) {
+        let day = rose.day();
+        let (due, later) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|d| d.day <= day);
+        self.pending = later;
+        for mut delivery in due {
+            let specs = std::mem::take(&mut delivery.items);
+            for spec in specs {
+                let sample = Item::new(spec.name.clone(), spec.sell_in, spec.quality);
+                match rose.validate_item(&sample) {
+                    Ok(()) => {
+                        rose.items.extend(spec.items());
+                        delivery.items.push(spec);
+                    }
+                    Err(reason) => report.rejected.push((spec, reason)),
+                }
+            }
+            if !delivery.items.is_empty() {
+                report.received.push(delivery);
+            }
+        }
+    }
+}
@@ -1,5 +1,34 @@
+use crate::advisor::Advisor;
+use crate::alert::{Alert, AlertRule, AlertSink, Alerts, FileSink, WebhookSink, WriterSink};
+use crate::alias::{self, Alias, AliasError, AliasKind, AliasTarget};
+use crate::appreciating::{AfterExpiry, Appreciating, Appreciation};
+use crate::behavior::{ItemBehavior, ItemContext};
+use crate::chart::{self, Selection};
+use crate::cli::{self, CliError, EventAction};
+use crate::console::Console;
+use crate::decay::{DecayCurve, Perishable};
+use crate::demand::{DemandRule, Distribution, MonteCarlo, Preference, Rng, TrialOutcome};
+use crate::diff::{self, Diff, Entry, Matching, Modified};
+use crate::event::{EventError, EventStatus, Refund, backstage_event};
+use crate::explain::Explanation;
+use crate::expr::{Env, EvalError, Expr, ExprRule, ParseError, RuleSet};
 use crate::gilded_rose::{GildedRose, Item};
-use crate::spec::{QUALITY_MAX, QUALITY_MIN, dec_to_floor, inc_to_cap};
+use crate::json::{Json, JsonError};
+use crate::legendary::{Legendary, QUALITY_SULFURAS};
+use crate::lint::{self, NearMiss};
+use crate::modifier::{Modifier, Modifiers, Step, builtin_modifiers};
+use crate::observer::{Bound, Observer};
+use crate::pattern::Selector;
+use crate::profile::{ConjuredPolicy, LegendaryPolicy, Profile, Thresholds};
+use crate::render::{self, DayReport, Format, Totals};
+use crate::rpc::{RpcError, Session, replay};
+use crate::sales::{Cents, Ledger, Pricing, ReceiptId, Sale, SaleError, SalePolicy, Unsellable};
+use crate::server::{Request, Server, Service};
+use crate::spec::{Kind, QUALITY_MAX, QUALITY_MIN, dec_to_floor, inc_to_cap};
+use crate::stock::{Stock, StockError, StockLine};
+use crate::stores::{STORE_TOTAL, StoreError, Stores, Transfer};
+use crate::supply::{Delivery, ItemSpec, ReorderRule, Supplier, Supply, SupplyError};
+use std::ops::RangeInclusive;
 
 fn mk(name: &str, sell_in: i32, quality: i32) -> Item {
     Item::new(name, sell_in, quality)
@@ -625,3 +654,3733 @@ fn backstage_day_before_concert_caps_then_zero_next_day() {
     tick(&mut r); // -> (-1, 0)
     assert_eq!((r.items[0].sell_in, r.items[0].quality), (-1, 0));
 }
+
+//
+// Profiles
+//
+
+fn rose_with_profile(items: Vec<Item>, profile: Profile) -> GildedRose {
+    GildedRose::with_profile(items, profile)
+}
+
+#[test]
+fn profiles_are_looked_up_by_name() {
+    assert_eq!(Profile::by_name("default"), Some(Profile::default()));
+    assert_eq!(Profile::by_name("classic"), Some(Profile::classic_kata()));
+    assert_eq!(Profile::by_name("Classic"), None);
+}
+
+#[test]
+fn classic_backstage_thresholds_are_exclusive() {
+    // Classic: <10 → +2 and <5 → +3, so 10 and 5 fall into the lower band.
+    for (si, inc) in [(11, 1), (10, 1), (9, 2), (5, 2), (4, 3), (1, 3)] {
+        let mut r = rose_with_profile(
+            vec![mk("Backstage passes to a TAFKAL80ETC concert", si, 10)],
+            Profile::classic_kata(),
+        );
+        tick(&mut r);
+        assert_eq!(r.items[0].quality, 10 + inc, "sell_in={si}");
+    }
+}
+
+#[test]
+fn classic_backstage_still_drops_to_zero_after_concert() {
+    let mut r = rose_with_profile(
+        vec![mk("Backstage passes to a TAFKAL80ETC concert", 0, 30)],
+        Profile::classic_kata(),
+    );
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (-1, 0));
+}
+
+#[test]
+fn classic_conjured_is_just_a_name() {
+    // Without Conjured support, "Conjured foo" is a normal item and "Conjured Aged Brie" is not Brie.
+    let mut r = rose_with_profile(
+        vec![mk("Conjured foo", 3, 10), mk("Conjured Aged Brie", 3, 10)],
+        Profile::classic_kata(),
+    );
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (2, 9));
+    assert_eq!((r.items[1].sell_in, r.items[1].quality), (2, 9));
+}
+
+#[test]
+fn classic_sell_in_wraps_at_min() {
+    // Wrapping makes the item look fresh again, so no expiry pass applies on that tick.
+    let mut r = rose_with_profile(vec![mk("foo", i32::MIN, 10)], Profile::classic_kata());
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (i32::MAX, 9));
+}
+
+#[test]
+fn forced_legendary_quality_is_reset_to_80() {
+    let profile = Profile {
+        legendary: LegendaryPolicy::Force,
+        ..Profile::default()
+    };
+    let mut r = rose_with_profile(vec![mk("Sulfuras, Hand of Ragnaros", 3, 12)], profile);
+    tick(&mut r);
+    assert_eq!(
+        (r.items[0].sell_in, r.items[0].quality),
+        (3, QUALITY_SULFURAS)
+    );
+}
+
+#[test]
+fn kept_legendary_quality_is_left_alone() {
+    let mut r = rose_with(vec![mk("Sulfuras, Hand of Ragnaros", 3, 12)]);
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (3, 12));
+}
+
+#[test]
+fn profile_fields_can_be_mixed() {
+    // Default rules except for exclusive thresholds: Conjured still degrades twice as fast.
+    let profile = Profile {
+        backstage_thresholds: Thresholds::Exclusive,
+        ..Profile::default()
+    };
+    let mut r = rose_with_profile(
+        vec![
+            mk("Backstage passes to a TAFKAL80ETC concert", 10, 10),
+            mk("Conjured foo", 3, 10),
+        ],
+        profile,
+    );
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 11);
+    assert_eq!(r.items[1].quality, 8);
+}
+
+//
+// Modifiers
+//
+
+#[test]
+fn conjured_conjured_normal_degrades_by_4_and_8_after_expiry() {
+    // Stacked Conjured multiplies: ×2 ×2.
+    let mut r = rose_with(vec![mk("Conjured Conjured foo", 1, 20)]);
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (0, 16));
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (-1, 8));
+}
+
+// The default profile plus Blessed, Cursed and Enchanted.
+fn modifiers_profile() -> Profile {
+    Profile {
+        modifiers: builtin_modifiers(),
+        ..Profile::default()
+    }
+}
+
+fn rose_with_modifiers(items: Vec<Item>) -> GildedRose {
+    rose_with_profile(items, modifiers_profile())
+}
+
+#[test]
+fn default_profile_leaves_other_prefixes_normal() {
+    // Only "Conjured " is on by default; Blessed, Cursed and Enchanted are opt-in.
+    let mut r = rose_with(vec![
+        mk("Blessed foo", 5, 10),
+        mk("Cursed foo", 5, 10),
+        mk("Enchanted Aged Brie", 5, 10),
+    ]);
+    tick(&mut r);
+    let qualities: Vec<i32> = r.items.iter().map(|it| it.quality).collect();
+    assert_eq!(qualities, [9, 9, 9]);
+    assert!(Profile::default().modifiers.is_empty());
+}
+
+#[test]
+fn blessed_normal_does_not_degrade() {
+    // Blessed takes one off each decrease: 1 - 1 = 0.
+    let mut r = rose_with_modifiers(vec![mk("Blessed foo", 0, 10)]);
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (-1, 10));
+}
+
+#[test]
+fn cursed_normal_degrades_by_2_and_4_after_expiry() {
+    let mut r = rose_with_modifiers(vec![mk("Cursed foo", 1, 10)]);
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 8);
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 4);
+}
+
+#[test]
+fn enchanted_brie_gains_twice_as_fast() {
+    let mut r = rose_with_modifiers(vec![mk("Enchanted Aged Brie", 1, 10)]);
+    tick(&mut r); // +2
+    assert_eq!(r.items[0].quality, 12);
+    tick(&mut r); // +2 daily, +2 expiry
+    assert_eq!(r.items[0].quality, 16);
+}
+
+#[test]
+fn enchanted_backstage_doubles_band_gains_but_still_drops_to_zero() {
+    for (si, inc) in [(12, 2), (10, 4), (5, 6)] {
+        let mut r = rose_with_modifiers(vec![mk("Enchanted Backstage passes - Hall", si, 10)]);
+        tick(&mut r);
+        assert_eq!(r.items[0].quality, 10 + inc, "sell_in={si}");
+    }
+    let mut r = rose_with_modifiers(vec![mk("Enchanted Backstage passes - Hall", 0, 40)]);
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 0);
+}
+
+#[test]
+fn modifiers_apply_in_name_order() {
+    // Cursed then Conjured: (1 + 1) × 2 = 4; Conjured then Cursed: 1 × 2 + 1 = 3.
+    let mut r = rose_with_modifiers(vec![
+        mk("Cursed Conjured foo", 5, 20),
+        mk("Conjured Cursed foo", 5, 20),
+    ]);
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 16);
+    assert_eq!(r.items[1].quality, 17);
+}
+
+#[test]
+fn blessed_and_cursed_cancel_out() {
+    let mut r = rose_with_modifiers(vec![
+        mk("Blessed Cursed foo", 5, 20),
+        mk("Cursed Blessed Aged Brie", 5, 20),
+    ]);
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 19);
+    assert_eq!(r.items[1].quality, 21);
+}
+
+#[test]
+fn cursed_brie_gain_never_turns_into_a_loss() {
+    // Cursed Cursed takes 2 off a +1 gain; the step is floored at 0 rather than flipping sign.
+    let mut r = rose_with_modifiers(vec![mk("Cursed Cursed Aged Brie", 5, 20)]);
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 20);
+}
+
+#[test]
+fn modifiers_do_not_affect_legendary() {
+    let mut r = rose_with_modifiers(vec![mk(
+        "Cursed Conjured Sulfuras, Hand of Ragnaros",
+        3,
+        80,
+    )]);
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (3, 80));
+}
+
+#[test]
+fn modifier_prefixes_are_case_sensitive_and_need_a_space() {
+    let mut r = rose_with_modifiers(vec![mk("blessed foo", 5, 10), mk("Blessedfoo", 5, 10)]);
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 9);
+    assert_eq!(r.items[1].quality, 9);
+}
+
+#[test]
+fn split_modifiers_reports_base_name_and_conjured_count() {
+    let p = modifiers_profile();
+    let (mods, base) = p.split_modifiers("Conjured Blessed Conjured Aged Brie");
+    assert_eq!(base, "Aged Brie");
+    assert_eq!(mods.conjured, 2);
+    // ((n × 2) − 1) × 2
+    assert_eq!(mods.degrade(1), 2);
+    assert_eq!(mods.degrade(3), 10);
+    assert_eq!(mods.appreciate(1), 2);
+}
+
+#[test]
+fn classic_profile_knows_no_modifiers() {
+    let (mods, base) = Profile::classic_kata().split_modifiers("Blessed Conjured foo");
+    assert_eq!(base, "Blessed Conjured foo");
+    assert_eq!(mods, Modifiers::NONE);
+}
+
+#[test]
+fn custom_modifiers_can_be_added_to_a_profile() {
+    let mut profile = Profile::default();
+    profile
+        .modifiers
+        .push(Modifier::new("Rusty ", Step::times(3), Step::IDENTITY));
+    let mut r = rose_with_profile(vec![mk("Rusty Conjured foo", 5, 20)], profile);
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 14);
+}
+
+#[test]
+fn step_composition_matches_sequential_application() {
+    let a = Step {
+        factor: 3,
+        offset: -1,
+    };
+    let b = Step {
+        factor: 2,
+        offset: 5,
+    };
+    for n in 0..10 {
+        assert_eq!(a.then(b).apply(n), (n * 3 - 1) * 2 + 5);
+    }
+    assert_eq!(Step::times(i32::MAX).apply(2), i32::MAX);
+}
+
+//
+// Conjured policy
+//
+
+fn conjured_profile(conjured: ConjuredPolicy) -> Profile {
+    Profile {
+        conjured,
+        ..Profile::default()
+    }
+}
+
+#[test]
+fn all_changes_conjured_brie_gains_twice_as_fast_in_both_passes() {
+    let mut r = rose_with_profile(
+        vec![mk("Conjured Aged Brie", 1, 10)],
+        conjured_profile(ConjuredPolicy::AllChanges),
+    );
+    tick(&mut r); // +2
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (0, 12));
+    tick(&mut r); // +2 daily, +2 expiry
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (-1, 16));
+}
+
+#[test]
+fn all_changes_conjured_backstage_doubles_band_gains_and_still_drops_to_zero() {
+    for (si, inc) in [(12, 2), (10, 4), (5, 6)] {
+        let mut r = rose_with_profile(
+            vec![mk("Conjured Backstage passes - Arena", si, 10)],
+            conjured_profile(ConjuredPolicy::AllChanges),
+        );
+        tick(&mut r);
+        assert_eq!(r.items[0].quality, 10 + inc, "sell_in={si}");
+    }
+    let mut r = rose_with_profile(
+        vec![mk("Conjured Backstage passes - Arena", 0, 40)],
+        conjured_profile(ConjuredPolicy::AllChanges),
+    );
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 0);
+}
+
+#[test]
+fn all_changes_conjured_normal_still_degrades_by_2_and_4() {
+    let mut r = rose_with_profile(
+        vec![mk("Conjured foo", 1, 10)],
+        conjured_profile(ConjuredPolicy::AllChanges),
+    );
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 8);
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 4);
+}
+
+#[test]
+fn custom_factor_degrade_only_leaves_gains_alone() {
+    let policy = ConjuredPolicy::Custom {
+        factor: 3,
+        amplify_gains: false,
+    };
+    let mut r = rose_with_profile(
+        vec![mk("Conjured foo", 0, 20), mk("Conjured Aged Brie", 0, 20)],
+        conjured_profile(policy),
+    );
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 14); // -3 daily, -3 expiry
+    assert_eq!(r.items[1].quality, 22); // +1 daily, +1 expiry
+}
+
+#[test]
+fn custom_factor_all_changes_scales_gains_and_caps_at_50() {
+    let policy = ConjuredPolicy::Custom {
+        factor: 3,
+        amplify_gains: true,
+    };
+    let mut r = rose_with_profile(
+        vec![
+            mk("Conjured Aged Brie", 0, 20),
+            mk("Conjured Backstage passes - Arena", 5, 44),
+        ],
+        conjured_profile(policy),
+    );
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 26); // +3 daily, +3 expiry
+    assert_eq!(r.items[1].quality, 50); // +9 capped
+}
+
+#[test]
+fn custom_factor_of_one_makes_conjured_a_no_op() {
+    let policy = ConjuredPolicy::Custom {
+        factor: 1,
+        amplify_gains: true,
+    };
+    let mut r = rose_with_profile(vec![mk("Conjured foo", 0, 20)], conjured_profile(policy));
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (-1, 18));
+}
+
+#[test]
+fn conjured_policy_combines_with_other_modifiers() {
+    // Enchanted ×2 then Conjured ×2 on gains under all-changes.
+    let mut r = rose_with_profile(
+        vec![mk("Enchanted Conjured Aged Brie", 5, 10)],
+        Profile {
+            modifiers: builtin_modifiers(),
+            ..conjured_profile(ConjuredPolicy::AllChanges)
+        },
+    );
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 14);
+}
+
+#[test]
+fn conjured_policy_parses_cli_values() {
+    assert_eq!(
+        ConjuredPolicy::parse("ignored"),
+        Some(ConjuredPolicy::Ignored)
+    );
+    assert_eq!(
+        ConjuredPolicy::parse("degrade-only"),
+        Some(ConjuredPolicy::DegradeOnly)
+    );
+    assert_eq!(
+        ConjuredPolicy::parse("all-changes"),
+        Some(ConjuredPolicy::AllChanges)
+    );
+    assert_eq!(
+        ConjuredPolicy::parse("all-changes:3"),
+        Some(ConjuredPolicy::Custom {
+            factor: 3,
+            amplify_gains: true
+        })
+    );
+    assert_eq!(
+        ConjuredPolicy::parse("degrade-only:4"),
+        Some(ConjuredPolicy::Custom {
+            factor: 4,
+            amplify_gains: false
+        })
+    );
+    for bad in ["", "ignored:2", "all-changes:", "all-changes:-1", "double"] {
+        assert_eq!(ConjuredPolicy::parse(bad), None, "{bad:?}");
+    }
+}
+
+//
+// Legendary registry
+//
+
+fn profile_with_legendaries(extra: &[(&'static str, i32)]) -> Profile {
+    let mut profile = Profile::default();
+    for &(pattern, quality) in extra {
+        profile.legendaries.push(Legendary::new(pattern, quality));
+    }
+    profile
+}
+
+#[test]
+fn registered_legendary_is_immutable() {
+    let profile = profile_with_legendaries(&[("Thunderfury, Blessed Blade", 90)]);
+    let mut r = rose_with_profile(vec![mk("Thunderfury, Blessed Blade", 2, 90)], profile);
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (2, 90));
+}
+
+#[test]
+fn registered_legendary_is_forced_to_its_own_quality() {
+    let mut profile = profile_with_legendaries(&[("Atiesh*", 65)]);
+    profile.legendary = LegendaryPolicy::Force;
+    let mut r = rose_with_profile(
+        vec![
+            mk("Atiesh, Greatstaff of the Guardian", 2, 10),
+            mk("Sulfuras, Hand of Ragnaros", 2, 10),
+        ],
+        profile,
+    );
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (2, 65));
+    assert_eq!((r.items[1].sell_in, r.items[1].quality), (2, 80));
+}
+
+#[test]
+fn legendary_patterns_apply_to_base_name_after_modifiers() {
+    let profile = profile_with_legendaries(&[("*Ashbringer", 75)]);
+    let mut r = rose_with_profile(
+        vec![
+            mk("Conjured The Ashbringer", 2, 75),
+            mk("Ashbringer replica", 2, 10),
+        ],
+        profile,
+    );
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (2, 75));
+    assert_eq!((r.items[1].sell_in, r.items[1].quality), (1, 9));
+}
+
+#[test]
+fn legendary_registry_takes_precedence_over_builtin_kinds() {
+    let profile = profile_with_legendaries(&[("Aged Brie", 42)]);
+    assert_eq!(profile.classify("Aged Brie"), Kind::Legendary(42));
+    assert_eq!(
+        Profile::default().classify("Aged Brie"),
+        Kind::Appreciating(Appreciation::AGED_BRIE)
+    );
+}
+
+#[test]
+fn first_matching_legendary_wins() {
+    let profile = profile_with_legendaries(&[("Sulfuras*", 99)]);
+    assert_eq!(
+        profile.classify("Sulfuras, Hand of Ragnaros"),
+        Kind::Legendary(80)
+    );
+    assert_eq!(profile.classify("Sulfuras, Fist"), Kind::Legendary(99));
+}
+
+#[test]
+fn empty_registry_makes_sulfuras_normal() {
+    let profile = Profile {
+        legendaries: Vec::new(),
+        ..Profile::default()
+    };
+    let mut r = rose_with_profile(vec![mk("Sulfuras, Hand of Ragnaros", 2, 40)], profile);
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (1, 39));
+}
+
+#[test]
+fn legendary_glob_matching() {
+    let cases = [
+        ("Sulfuras*", "Sulfuras", true),
+        ("Sulfuras*", "Sulfuras, Hand of Ragnaros", true),
+        ("*Ragnaros", "Sulfuras, Hand of Ragnaros", true),
+        ("S*s, H*", "Sulfuras, Hand of Ragnaros", true),
+        ("*a*a*a*", "banana", true),
+        ("*", "", true),
+        ("Sulfuras*", "sulfuras", false),
+        ("*Ragnaros", "Sulfuras, Hand of Ragnaros ", false),
+        ("S*x", "Sulfuras", false),
+        ("Sulfuras", "Sulfuras, Hand of Ragnaros", false),
+    ];
+    for (pattern, name, expected) in cases {
+        let l = Legendary::new(pattern, 80);
+        assert_eq!(l.matches(name), expected, "{pattern:?} vs {name:?}");
+    }
+}
+
+#[test]
+fn legendary_parses_cli_values() {
+    let l = Legendary::parse("Thunderfury*=90").expect("valid spec");
+    assert_eq!((l.pattern.as_ref(), l.quality), ("Thunderfury*", 90));
+    let l = Legendary::parse("a=b=7").expect("last '=' separates quality");
+    assert_eq!((l.pattern.as_ref(), l.quality), ("a=b", 7));
+    for bad in ["Thunderfury", "=90", "Thunderfury=", "Thunderfury=x"] {
+        assert_eq!(Legendary::parse(bad), None, "{bad:?}");
+    }
+}
+
+//
+// Appreciating goods
+//
+
+fn profile_with_appreciating(pattern: &'static str, rule: Appreciation) -> Profile {
+    let mut profile = Profile::default();
+    profile.appreciating.push(Appreciating::new(pattern, rule));
+    profile
+}
+
+#[test]
+fn continue_rule_gains_rate_plus_extra_after_expiry() {
+    let rule = Appreciation {
+        rate: 2,
+        after_expiry: AfterExpiry::Continue { extra: 3 },
+    };
+    let mut r = rose_with_profile(
+        vec![mk("Port wine", 1, 10)],
+        profile_with_appreciating("Port*", rule),
+    );
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (0, 12));
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (-1, 17));
+}
+
+#[test]
+fn plateau_rule_stops_gaining_on_sell_by_date() {
+    let rule = Appreciation {
+        rate: 2,
+        after_expiry: AfterExpiry::Plateau,
+    };
+    let mut r = rose_with_profile(
+        vec![mk("Gouda", 2, 10)],
+        profile_with_appreciating("Gouda", rule),
+    );
+    tick(&mut r);
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (0, 14));
+    tick(&mut r);
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (-2, 14));
+}
+
+#[test]
+fn spoil_rule_degrades_after_expiry_and_respects_floor() {
+    let rule = Appreciation {
+        rate: 1,
+        after_expiry: AfterExpiry::Spoil { rate: 3 },
+    };
+    let mut r = rose_with_profile(
+        vec![mk("Camembert", 1, 5)],
+        profile_with_appreciating("Camembert", rule),
+    );
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (0, 6));
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (-1, 3));
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (-2, 0));
+}
+
+#[test]
+fn conjured_spoiling_good_spoils_twice_as_fast() {
+    // Spoiling is a decrease, so degrade-only Conjured doubles it while gains stay at +1.
+    let rule = Appreciation {
+        rate: 1,
+        after_expiry: AfterExpiry::Spoil { rate: 2 },
+    };
+    let mut r = rose_with_profile(
+        vec![mk("Conjured Camembert", 1, 20)],
+        profile_with_appreciating("Camembert", rule),
+    );
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 21);
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 17);
+}
+
+#[test]
+fn appreciating_goods_cap_at_50() {
+    let rule = Appreciation {
+        rate: 5,
+        after_expiry: AfterExpiry::Continue { extra: 5 },
+    };
+    let mut r = rose_with_profile(
+        vec![mk("Bordeaux", 0, 45)],
+        profile_with_appreciating("Bordeaux", rule),
+    );
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 50);
+}
+
+#[test]
+fn aged_brie_is_the_builtin_appreciating_good() {
+    let profile = Profile::default();
+    assert_eq!(
+        profile.classify("Aged Brie"),
+        Kind::Appreciating(Appreciation::AGED_BRIE)
+    );
+    assert_eq!(profile.classify("Aged Brie "), Kind::Normal);
+}
+
+#[test]
+fn aged_brie_can_be_overridden_by_an_earlier_entry() {
+    let mut profile = Profile::default();
+    profile.appreciating.insert(
+        0,
+        Appreciating::new(
+            "Aged Brie",
+            Appreciation {
+                rate: 1,
+                after_expiry: AfterExpiry::Plateau,
+            },
+        ),
+    );
+    let mut r = rose_with_profile(vec![mk("Aged Brie", 0, 10)], profile);
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (-1, 10));
+}
+
+#[test]
+fn appreciation_parses_cli_values() {
+    let parse = |s| Appreciating::parse(s).map(|a| (a.pattern.into_owned(), a.rule));
+    let rule = |rate, after_expiry| Appreciation { rate, after_expiry };
+    assert_eq!(
+        parse("Port*=2"),
+        Some(("Port*".into(), rule(2, AfterExpiry::Continue { extra: 0 })))
+    );
+    assert_eq!(
+        parse("Port*=2:continue:1"),
+        Some(("Port*".into(), rule(2, AfterExpiry::Continue { extra: 1 })))
+    );
+    assert_eq!(
+        parse("Gouda=1:plateau"),
+        Some(("Gouda".into(), rule(1, AfterExpiry::Plateau)))
+    );
+    assert_eq!(
+        parse("Brie=1:spoil:2"),
+        Some(("Brie".into(), rule(1, AfterExpiry::Spoil { rate: 2 })))
+    );
+    for bad in [
+        "Port",
+        "=1",
+        "Port=-1",
+        "Port=1:plateau:2",
+        "Port=1:spoil",
+        "Port=1:melt",
+    ] {
+        assert_eq!(parse(bad), None, "{bad:?}");
+    }
+}
+
+//
+// Events
+//
+
+const PASS_TAFKAL: &str = "Backstage passes to a TAFKAL80ETC concert";
+const EVENT_TAFKAL: &str = "TAFKAL80ETC concert";
+
+#[test]
+fn backstage_event_is_parsed_from_the_pass_name() {
+    assert_eq!(backstage_event(PASS_TAFKAL), Some(EVENT_TAFKAL));
+    assert_eq!(
+        backstage_event("Backstage passes to an Opera"),
+        Some("Opera")
+    );
+    assert_eq!(
+        backstage_event("Backstage passes to the Gala"),
+        Some("Gala")
+    );
+    assert_eq!(
+        backstage_event("Backstage passes for Arena night"),
+        Some("Arena night")
+    );
+    assert_eq!(backstage_event("Backstage passes - Hall"), Some("Hall"));
+    assert_eq!(backstage_event("Backstage passes"), None);
+    assert_eq!(backstage_event("Backstage passes to a "), None);
+    assert_eq!(backstage_event("Backstage passesHall"), None);
+    assert_eq!(backstage_event("Front row to a concert"), None);
+}
+
+#[test]
+fn scheduling_an_event_aligns_all_its_passes() {
+    let mut r = rose_with(vec![
+        mk(PASS_TAFKAL, 15, 20),
+        mk(PASS_TAFKAL, 5, 20),
+        mk("Conjured Backstage passes to a TAFKAL80ETC concert", 1, 20),
+        mk("Backstage passes - Hall", 5, 20),
+    ]);
+    r.schedule_event(EVENT_TAFKAL, 10);
+    let sell_ins: Vec<i32> = r.items.iter().map(|it| it.sell_in).collect();
+    assert_eq!(sell_ins, [10, 10, 10, 5]);
+    tick(&mut r);
+    let state: Vec<(i32, i32)> = r.items.iter().map(|it| (it.sell_in, it.quality)).collect();
+    assert_eq!(state, [(9, 22), (9, 22), (9, 22), (4, 23)]);
+    assert_eq!(
+        r.events().status(EVENT_TAFKAL),
+        Some(EventStatus::Scheduled { days_left: 9 })
+    );
+}
+
+#[test]
+fn event_date_is_authoritative_over_a_stray_sell_in() {
+    let mut r = rose_with(vec![mk(PASS_TAFKAL, 3, 20)]);
+    r.schedule_event(EVENT_TAFKAL, 8);
+    r.items[0].sell_in = 1; // edited by hand; the event date wins on the next tick
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (7, 22));
+}
+
+#[test]
+fn postponing_moves_all_passes_and_delays_the_drop() {
+    let mut r = rose_with(vec![mk(PASS_TAFKAL, 1, 20), mk(PASS_TAFKAL, 1, 30)]);
+    r.schedule_event(EVENT_TAFKAL, 1);
+    r.postpone_event(EVENT_TAFKAL, 10).expect("scheduled event");
+    assert!(r.items.iter().all(|it| it.sell_in == 11));
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (10, 21));
+    assert_eq!((r.items[1].sell_in, r.items[1].quality), (10, 31));
+}
+
+#[test]
+fn cancelling_refunds_and_zeroes_passes_for_good() {
+    let mut r = rose_with(vec![
+        mk(PASS_TAFKAL, 5, 20),
+        mk("Aged Brie", 5, 20),
+        mk(PASS_TAFKAL, 5, 45),
+    ]);
+    r.schedule_event(EVENT_TAFKAL, 5);
+    let refunds = r.cancel_event(EVENT_TAFKAL).expect("scheduled event");
+    assert_eq!(
+        refunds,
+        [
+            Refund {
+                index: 0,
+                name: PASS_TAFKAL.to_string(),
+                quality: 20
+            },
+            Refund {
+                index: 2,
+                name: PASS_TAFKAL.to_string(),
+                quality: 45
+            },
+        ]
+    );
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (4, 0));
+    assert_eq!((r.items[1].sell_in, r.items[1].quality), (4, 21));
+    assert_eq!((r.items[2].sell_in, r.items[2].quality), (4, 0));
+    assert_eq!(
+        r.events().status(EVENT_TAFKAL),
+        Some(EventStatus::Cancelled)
+    );
+}
+
+#[test]
+fn event_errors_for_unknown_and_cancelled_events() {
+    let mut r = rose_with(vec![mk(PASS_TAFKAL, 5, 20)]);
+    assert_eq!(
+        r.postpone_event(EVENT_TAFKAL, 1),
+        Err(EventError::Unknown(EVENT_TAFKAL.to_string()))
+    );
+    assert_eq!(
+        r.cancel_event("Opera"),
+        Err(EventError::Unknown("Opera".to_string()))
+    );
+    r.schedule_event(EVENT_TAFKAL, 5);
+    r.cancel_event(EVENT_TAFKAL).expect("scheduled event");
+    assert_eq!(
+        r.cancel_event(EVENT_TAFKAL),
+        Err(EventError::Cancelled(EVENT_TAFKAL.to_string()))
+    );
+    assert_eq!(
+        r.postpone_event(EVENT_TAFKAL, 1),
+        Err(EventError::Cancelled(EVENT_TAFKAL.to_string()))
+    );
+    // Re-scheduling revives it.
+    r.schedule_event(EVENT_TAFKAL, 3);
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (2, 3));
+}
+
+#[test]
+fn unregistered_events_keep_their_own_sell_in() {
+    let mut r = rose_with(vec![mk(PASS_TAFKAL, 5, 20), mk(PASS_TAFKAL, 12, 20)]);
+    r.schedule_event("Opera", 1);
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (4, 23));
+    assert_eq!((r.items[1].sell_in, r.items[1].quality), (11, 21));
+}
+
+#[test]
+fn passes_drop_to_zero_the_day_after_the_event() {
+    let mut r = rose_with(vec![mk(PASS_TAFKAL, 9, 20)]);
+    r.schedule_event(EVENT_TAFKAL, 0);
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (-1, 0));
+    assert_eq!(
+        r.events().status(EVENT_TAFKAL),
+        Some(EventStatus::Scheduled { days_left: -1 })
+    );
+}
+
+#[test]
+fn event_names_only_match_backstage_passes() {
+    // A normal item whose name happens to end with the event name is untouched by the registry.
+    let mut r = rose_with(vec![mk("Poster to a TAFKAL80ETC concert", 5, 20)]);
+    r.schedule_event(EVENT_TAFKAL, 1);
+    let refunds = r.cancel_event(EVENT_TAFKAL).expect("scheduled event");
+    assert!(refunds.is_empty());
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (4, 19));
+}
+
+#[test]
+fn cli_parses_event_flags() {
+    let args = [
+        "--event",
+        "TAFKAL80ETC concert=12",
+        "--postpone",
+        "TAFKAL80ETC concert=3@2",
+        "--cancel",
+        "Opera@5",
+    ];
+    let opts = cli::parse(args.map(String::from)).expect("valid flags");
+    assert!(opts.profile.is_none());
+    assert_eq!(opts.events, [(EVENT_TAFKAL.to_string(), 12)]);
+    assert_eq!(
+        opts.event_actions,
+        [
+            (
+                2,
+                EventAction::Postpone {
+                    event: EVENT_TAFKAL.to_string(),
+                    days: 3
+                }
+            ),
+            (
+                5,
+                EventAction::Cancel {
+                    event: "Opera".to_string()
+                }
+            ),
+        ]
+    );
+    for bad in [
+        &["--event", "Opera"][..],
+        &["--postpone", "Opera=3"],
+        &["--cancel", "Opera"],
+        &["--event"],
+        &["--bogus", "x"],
+    ] {
+        assert!(
+            cli::parse(bad.iter().map(|s| s.to_string())).is_err_and(|e| e == CliError::Usage),
+            "{bad:?}"
+        );
+    }
+}
+
+//
+// Decay curves
+//
+
+fn profile_with_curve(pattern: &'static str, curve: DecayCurve) -> Profile {
+    let mut profile = Profile::default();
+    profile.perishables.push(Perishable {
+        pattern: pattern.into(),
+        curve,
+    });
+    profile
+}
+
+#[test]
+fn linear_curve_sets_the_daily_rate_and_doubles_after_expiry() {
+    let profile = Profile {
+        modifiers: builtin_modifiers(),
+        ..profile_with_curve("Fish", DecayCurve::Linear { rate: 3 })
+    };
+    let mut r = rose_with_profile(vec![mk("Fish", 1, 20)], profile);
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (0, 17));
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (-1, 11));
+}
+
+#[test]
+fn stepped_curve_accelerates_as_sell_in_shrinks() {
+    let curve = DecayCurve::Stepped {
+        bands: vec![(6, 1), (3, 2)],
+        otherwise: 4,
+    };
+    for (si, step) in [(10, 1), (6, 1), (5, 2), (3, 2), (2, 4), (1, 4)] {
+        assert_eq!(curve.step(si, 30), step, "sell_in={si}");
+    }
+    let mut r = rose_with_profile(
+        vec![mk("Lettuce", 7, 30)],
+        profile_with_curve("Lettuce", curve),
+    );
+    let mut qualities = Vec::new();
+    for _ in 0..8 {
+        tick(&mut r);
+        qualities.push(r.items[0].quality);
+    }
+    // 7,6 → -1; 5,4,3 → -2; 2,1 → -4; 0 → -4 -4 (expired)
+    assert_eq!(qualities, [29, 28, 26, 24, 22, 18, 14, 6]);
+}
+
+#[test]
+fn stepped_curve_parsed_out_of_order_matches_in_order() {
+    let curve = DecayCurve::parse("stepped:0:3,6:1,3:2,*:4").expect("curve");
+    let steps: Vec<i32> = [8, 6, 4, 3, 1, 0, -1].map(|s| curve.step(s, 50)).to_vec();
+    assert_eq!(steps, [1, 1, 2, 2, 3, 3, 4]);
+}
+
+#[test]
+fn percent_curve_rounds_up_and_respects_minimum() {
+    let curve = DecayCurve::Percent {
+        percent: 20,
+        min: 0,
+    };
+    assert_eq!(curve.step(5, 50), 10);
+    assert_eq!(curve.step(5, 12), 3); // 2.4 → 3
+    assert_eq!(curve.step(5, 1), 1); // 0.2 → 1
+    assert_eq!(curve.step(5, 0), 0);
+    assert_eq!(curve.step(5, i32::MAX), 429_496_730);
+    let with_min = DecayCurve::Percent {
+        percent: 10,
+        min: 2,
+    };
+    assert_eq!(with_min.step(5, 5), 2);
+    assert_eq!(with_min.step(5, 50), 5);
+}
+
+#[test]
+fn percent_curve_decays_fast_then_slow_and_reaches_zero() {
+    let profile = profile_with_curve(
+        "Berries",
+        DecayCurve::Percent {
+            percent: 50,
+            min: 0,
+        },
+    );
+    let mut r = rose_with_profile(vec![mk("Berries", 10, 40)], profile);
+    let mut qualities = Vec::new();
+    for _ in 0..7 {
+        tick(&mut r);
+        qualities.push(r.items[0].quality);
+    }
+    assert_eq!(qualities, [20, 10, 5, 2, 1, 0, 0]);
+}
+
+#[test]
+fn table_curve_uses_exact_entries_and_fallback() {
+    let curve = DecayCurve::Table {
+        rates: vec![(2, 5), (1, 7)],
+        otherwise: 1,
+    };
+    assert_eq!(curve.step(3, 30), 1);
+    assert_eq!(curve.step(2, 30), 5);
+    assert_eq!(curve.step(1, 30), 7);
+    assert_eq!(curve.step(0, 30), 1);
+}
+
+#[test]
+fn curve_steps_are_never_negative() {
+    assert_eq!(DecayCurve::Linear { rate: -3 }.step(5, 10), 0);
+    assert_eq!(
+        DecayCurve::Percent {
+            percent: -50,
+            min: 0
+        }
+        .step(5, 10),
+        0
+    );
+}
+
+#[test]
+fn curves_respect_floor_and_modifiers() {
+    let profile = Profile {
+        modifiers: builtin_modifiers(),
+        ..profile_with_curve("Fish", DecayCurve::Linear { rate: 3 })
+    };
+    let mut r = rose_with_profile(
+        vec![mk("Conjured Fish", 5, 5), mk("Blessed Fish", 5, 5)],
+        profile,
+    );
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 0); // 3 × 2 = 6, floored
+    assert_eq!(r.items[1].quality, 3); // 3 − 1
+}
+
+#[test]
+fn unregistered_names_keep_the_flat_normal_decay() {
+    let profile = profile_with_curve("Fish", DecayCurve::Linear { rate: 3 });
+    assert_eq!(
+        profile.classify("Fish"),
+        Kind::Perishable(&DecayCurve::Linear { rate: 3 })
+    );
+    assert_eq!(profile.classify("Fishing rod"), Kind::Normal);
+}
+
+#[test]
+fn decay_curves_parse_cli_values() {
+    let parse = |s| Perishable::parse(s).map(|p| p.curve);
+    assert_eq!(parse("Fish=linear:3"), Some(DecayCurve::Linear { rate: 3 }));
+    assert_eq!(
+        parse("Fish=percent:20"),
+        Some(DecayCurve::Percent {
+            percent: 20,
+            min: 0
+        })
+    );
+    assert_eq!(
+        parse("Fish=percent:20:2"),
+        Some(DecayCurve::Percent {
+            percent: 20,
+            min: 2
+        })
+    );
+    assert_eq!(
+        parse("Fish=stepped:6:1,3:2,*:4"),
+        Some(DecayCurve::Stepped {
+            bands: vec![(6, 1), (3, 2)],
+            otherwise: 4
+        })
+    );
+    // Bands are sorted from the highest bound down, whatever the input order.
+    assert_eq!(
+        parse("Fish=stepped:3:2,6:1,*:4"),
+        parse("Fish=stepped:6:1,3:2,*:4")
+    );
+    assert_eq!(
+        parse("Fish=table:2:5,1:7"),
+        Some(DecayCurve::Table {
+            rates: vec![(2, 5), (1, 7)],
+            otherwise: 0
+        })
+    );
+    for bad in [
+        "Fish",
+        "=linear:1",
+        "Fish=linear",
+        "Fish=cubic:2",
+        "Fish=stepped:6",
+        "Fish=table:a:1",
+    ] {
+        assert_eq!(parse(bad), None, "{bad:?}");
+    }
+}
+
+//
+// Stock lines
+//
+
+fn line(name: &str, sell_in: i32, quality: i32, count: u32) -> StockLine {
+    StockLine::new(mk(name, sell_in, quality), count)
+}
+
+fn stock_state(stock: &Stock) -> Vec<(String, i32, i32, u32)> {
+    stock
+        .lines()
+        .map(|(it, n)| (it.name.clone(), it.sell_in, it.quality, n))
+        .collect()
+}
+
+#[test]
+fn stock_line_ages_once_regardless_of_count() {
+    let mut s = Stock::new(vec![line("+5 Dexterity Vest", 10, 20, 40)]);
+    s.update_quality();
+    assert_eq!(stock_state(&s), [("+5 Dexterity Vest".into(), 9, 19, 40)]);
+    assert_eq!(s.total_units(), 40);
+}
+
+#[test]
+fn identical_lines_are_merged_on_creation() {
+    let s = Stock::new(vec![
+        line("foo", 5, 10, 3),
+        line("bar", 5, 10, 1),
+        line("foo", 5, 10, 4),
+        line("foo", 5, 9, 2),
+    ]);
+    assert_eq!(
+        stock_state(&s),
+        [
+            ("foo".into(), 5, 10, 7),
+            ("bar".into(), 5, 10, 1),
+            ("foo".into(), 5, 9, 2),
+        ]
+    );
+}
+
+#[test]
+fn lines_that_become_identical_after_aging_are_merged() {
+    // Both lines hit the floor at 0 on the same sell_in.
+    let mut s = Stock::new(vec![line("foo", 1, 1, 2), line("foo", 1, 0, 3)]);
+    s.update_quality();
+    assert_eq!(stock_state(&s), [("foo".into(), 0, 0, 5)]);
+}
+
+#[test]
+fn stock_forwards_events_to_its_lines() {
+    let pass = PASS_TAFKAL;
+    let mut s = Stock::new(vec![line(pass, 15, 20, 4), line(pass, 12, 20, 2)]);
+    s.schedule_event(EVENT_TAFKAL, 10);
+    assert_eq!(stock_state(&s)[1], (pass.into(), 10, 20, 2));
+    let refunds = s.cancel_event(EVENT_TAFKAL).expect("scheduled");
+    assert_eq!(refunds.iter().map(|r| r.index).collect::<Vec<_>>(), [0, 1]);
+    assert_eq!(s.line(1).map(|(_, n)| n), Some(2));
+    s.update_quality();
+    assert_eq!(stock_state(&s), [(pass.into(), 9, 0, 6)]);
+}
+
+#[test]
+fn damaged_units_can_be_split_off_and_changed() {
+    let mut s = Stock::new(vec![line("Aged Brie", 5, 20, 10)]);
+    let damaged = s.split(0, 3).expect("enough units");
+    assert_eq!(damaged, 1);
+    s.item_mut(damaged).expect("new line").quality = 5;
+    s.update_quality();
+    assert_eq!(
+        stock_state(&s),
+        [
+            ("Aged Brie".into(), 4, 21, 7),
+            ("Aged Brie".into(), 4, 6, 3)
+        ]
+    );
+}
+
+#[test]
+fn an_unchanged_split_merges_back() {
+    let mut s = Stock::new(vec![line("foo", 5, 20, 10)]);
+    s.split(0, 4).expect("enough units");
+    assert_eq!(s.len(), 2);
+    s.merge();
+    assert_eq!(stock_state(&s), [("foo".into(), 5, 20, 10)]);
+}
+
+#[test]
+fn take_decrements_and_removes_exhausted_lines() {
+    let mut s = Stock::new(vec![line("foo", 5, 20, 3), line("bar", 5, 20, 1)]);
+    let sold = s.take(0, 2).expect("enough units");
+    assert_eq!(
+        (sold.item.name.as_str(), sold.item.quality, sold.count),
+        ("foo", 20, 2)
+    );
+    assert_eq!(s.line(0).map(|(_, n)| n), Some(1));
+    let sold = s.take(1, 1).expect("enough units");
+    assert_eq!(sold.count, 1);
+    assert_eq!(stock_state(&s), [("foo".into(), 5, 20, 1)]);
+}
+
+#[test]
+fn stock_rejects_bad_lines_and_counts() {
+    let mut s = Stock::new(vec![line("foo", 5, 20, 3)]);
+    assert_eq!(s.split(1, 1), Err(StockError::NoSuchLine(1)));
+    assert!(s.item_mut(9).is_err());
+    let invalid = |requested| StockError::InvalidCount {
+        line: 0,
+        available: 3,
+        requested,
+    };
+    assert_eq!(s.split(0, 0), Err(invalid(0)));
+    assert_eq!(s.split(0, 3), Err(invalid(3))); // a split must leave one behind
+    assert!(matches!(s.take(0, 4), Err(e) if e == invalid(4)));
+    assert!(matches!(s.take(0, 0), Err(e) if e == invalid(0)));
+    assert_eq!(s.total_units(), 3);
+}
+
+#[test]
+fn empty_and_zero_count_lines() {
+    let mut s = Stock::new(vec![line("foo", 5, 20, 0)]);
+    assert!(s.is_empty());
+    s.push(line("foo", 5, 20, 2));
+    s.push(line("foo", 5, 20, 1));
+    assert_eq!(stock_state(&s), [("foo".into(), 5, 20, 3)]);
+    s.update_quality();
+    assert_eq!(s.total_units(), 3);
+}
+
+#[test]
+fn stock_uses_its_profile() {
+    let mut s = Stock::with_profile(
+        vec![line("Conjured foo", 5, 20, 2)],
+        Profile::classic_kata(),
+    );
+    s.update_quality();
+    assert_eq!(stock_state(&s), [("Conjured foo".into(), 4, 19, 2)]);
+    assert!(s.rose().events().iter().next().is_none());
+}
+
+//
+// Sales
+//
+
+#[test]
+fn day_counter_advances_with_each_update() {
+    let mut r = rose_with(vec![]);
+    assert_eq!(r.day(), 0);
+    tick(&mut r);
+    tick(&mut r);
+    assert_eq!(r.day(), 2);
+}
+
+#[test]
+fn selling_removes_the_item_and_records_the_sale() {
+    let mut r = rose_with(vec![mk("foo", 5, 10), mk("Aged Brie", 5, 20)]);
+    tick(&mut r);
+    let mut ledger = Ledger::default();
+    let sale = ledger.sell(&mut r, 1).expect("sellable").clone();
+    assert_eq!(
+        sale,
+        Sale {
+            receipt: ReceiptId(1),
+            day: 1,
+            name: "Aged Brie".to_string(),
+            sell_in: 4,
+            quality: 21,
+            count: 1,
+            unit_price_cents: 100 + 50 * 21,
+        }
+    );
+    assert_eq!(r.items.len(), 1);
+    assert_eq!(r.items[0].name, "foo");
+}
+
+#[test]
+fn receipts_are_sequential_and_ledger_is_grouped_by_day() {
+    let mut r = rose_with(vec![mk("a", 5, 10), mk("b", 5, 10), mk("c", 5, 10)]);
+    let mut ledger = Ledger::default();
+    ledger.sell(&mut r, 0).expect("sellable");
+    tick(&mut r);
+    ledger.sell(&mut r, 0).expect("sellable");
+    ledger.sell(&mut r, 0).expect("sellable");
+    let receipts: Vec<String> = ledger
+        .sales()
+        .iter()
+        .map(|s| s.receipt.to_string())
+        .collect();
+    assert_eq!(receipts, ["R000001", "R000002", "R000003"]);
+    assert_eq!(ledger.day(0).count(), 1);
+    let day1: Vec<&str> = ledger.day(1).map(|s| s.name.as_str()).collect();
+    assert_eq!(day1, ["b", "c"]);
+    assert_eq!(ledger.day_total_cents(1), 2 * (100 + 50 * 9));
+    assert_eq!(ledger.day_total_cents(2), 0);
+}
+
+#[test]
+fn policy_refuses_legendary_and_worthless_items_by_default() {
+    let mut r = rose_with(vec![
+        mk("Sulfuras, Hand of Ragnaros", 0, 80),
+        mk("Backstage passes to a TAFKAL80ETC concert", -1, 0),
+    ]);
+    let mut ledger = Ledger::default();
+    assert_eq!(
+        ledger.sell(&mut r, 0).map(|s| s.receipt),
+        Err(SaleError::Unsellable {
+            index: 0,
+            reason: Unsellable::Legendary
+        })
+    );
+    assert_eq!(
+        ledger.sell(&mut r, 1).map(|s| s.receipt),
+        Err(SaleError::Unsellable {
+            index: 1,
+            reason: Unsellable::Worthless
+        })
+    );
+    assert_eq!(
+        ledger.sell(&mut r, 2).map(|s| s.receipt),
+        Err(SaleError::NoSuchItem(2))
+    );
+    assert_eq!(r.items.len(), 2);
+    assert!(ledger.sales().is_empty());
+}
+
+#[test]
+fn policy_can_refuse_expired_and_allow_legendary() {
+    let policy = SalePolicy {
+        sell_legendary: true,
+        sell_expired: false,
+        min_quality: 0,
+    };
+    let mut r = rose_with(vec![
+        mk("Sulfuras, Hand of Ragnaros", -1, 80),
+        mk("foo", -1, 0),
+    ]);
+    let mut ledger = Ledger::new(Pricing::default(), policy);
+    assert_eq!(
+        ledger.sell(&mut r, 1).map(|s| s.receipt),
+        Err(SaleError::Unsellable {
+            index: 1,
+            reason: Unsellable::Expired
+        })
+    );
+    let sale = ledger.sell(&mut r, 0).expect("legendary allowed");
+    assert_eq!(sale.unit_price_cents, Pricing::default().legendary_cents);
+}
+
+#[test]
+fn quote_uses_the_profile_kind() {
+    let ledger = Ledger::default();
+    let profile = Profile::default();
+    assert_eq!(
+        ledger.quote(&profile, &mk("Conjured Aged Brie", 3, 10)),
+        Ok(600)
+    );
+    assert_eq!(
+        ledger.quote(&profile, &mk("Conjured Sulfuras, Hand of Ragnaros", 3, 80)),
+        Err(Unsellable::Legendary)
+    );
+    // Under the classic profile "Conjured Sulfuras, ..." is just a normal item.
+    assert_eq!(
+        ledger.quote(
+            &Profile::classic_kata(),
+            &mk("Conjured Sulfuras, Hand of Ragnaros", 3, 10)
+        ),
+        Ok(600)
+    );
+}
+
+#[test]
+fn selling_units_decrements_a_stock_line() {
+    let mut s = Stock::new(vec![line("+5 Dexterity Vest", 10, 20, 40)]);
+    let mut ledger = Ledger::default();
+    let sale = ledger.sell_units(&mut s, 0, 3).expect("enough units");
+    assert_eq!((sale.count, sale.total_cents()), (3, 3 * 1100));
+    assert_eq!(s.total_units(), 37);
+    assert_eq!(
+        ledger.sell_units(&mut s, 0, 38).map(|s| s.receipt),
+        Err(SaleError::Stock(StockError::InvalidCount {
+            line: 0,
+            available: 37,
+            requested: 38
+        }))
+    );
+    ledger.sell_units(&mut s, 0, 37).expect("enough units");
+    assert!(s.is_empty());
+    assert_eq!(ledger.sales().len(), 2);
+}
+
+#[test]
+fn cents_format_with_two_decimals() {
+    assert_eq!(Cents(0).to_string(), "0.00");
+    assert_eq!(Cents(5).to_string(), "0.05");
+    assert_eq!(Cents(1234).to_string(), "12.34");
+}
+
+//
+// Deliveries and reordering
+//
+
+fn supply_with(supplier: &str, lead_time: u32, rule: &str) -> Supply {
+    let mut supply = Supply::default();
+    supply.add_supplier(Supplier {
+        name: supplier.to_string(),
+        lead_time,
+    });
+    supply
+        .add_rule(ReorderRule::parse(rule).expect("valid rule"))
+        .expect("known supplier");
+    supply
+}
+
+#[test]
+fn item_spec_parse() {
+    assert_eq!(
+        ItemSpec::parse("Aged Brie=10,0,3"),
+        Some(ItemSpec::new("Aged Brie", 10, 0, 3))
+    );
+    assert_eq!(
+        ItemSpec::parse("Elixir=5,-1"),
+        Some(ItemSpec::new("Elixir", 5, -1, 1))
+    );
+    assert_eq!(ItemSpec::parse("=5,7"), None);
+    assert_eq!(ItemSpec::parse("Elixir=5"), None);
+    assert_eq!(ItemSpec::parse("Elixir=5,7,1,2"), None);
+}
+
+#[test]
+fn delivery_arrives_at_the_start_of_its_day() {
+    let mut r = rose_with(vec![mk("Elixir of the Mongoose", 5, 7)]);
+    let mut supply = Supply::default();
+    supply.schedule(Delivery::parse("Aged Brie=10,0,2@1").expect("valid delivery"));
+
+    assert!(supply.start_day(&mut r).received.is_empty());
+    assert_eq!(r.items.len(), 1);
+    tick(&mut r);
+
+    let report = supply.start_day(&mut r);
+    assert_eq!(report.received.len(), 1);
+    assert!(supply.pending().is_empty());
+    assert_eq!(r.items.len(), 3);
+    // Delivered goods start fresh; they have not aged yet.
+    assert_eq!((r.items[1].sell_in, r.items[1].quality), (10, 0));
+    assert_eq!(r.items[2].name, "Aged Brie");
+}
+
+#[test]
+fn invalid_delivered_specs_are_rejected_not_stocked() {
+    let mut r = rose_with(vec![]);
+    let mut supply = Supply::default();
+    supply.schedule(Delivery::new(
+        0,
+        vec![
+            ItemSpec::new("Elixir", 5, 60, 2),
+            ItemSpec::new("Aged Brie", 3, 10, 1),
+            ItemSpec::new("Sulfuras, Hand of Ragnaros", 0, 50, 1),
+        ],
+    ));
+    supply.schedule(Delivery::parse("Elixir=5,-1@0").expect("valid delivery"));
+    let report = supply.start_day(&mut r);
+    assert_eq!(report.received.len(), 1);
+    assert_eq!(
+        report.received[0].items,
+        [ItemSpec::new("Aged Brie", 3, 10, 1)]
+    );
+    let rejected: Vec<&str> = report
+        .rejected
+        .iter()
+        .map(|(s, _)| s.name.as_str())
+        .collect();
+    assert_eq!(rejected, ["Elixir", "Sulfuras, Hand of Ragnaros", "Elixir"]);
+    assert_eq!(r.items.len(), 1);
+    // Nothing out of range was stocked, so updating does not trip the preconditions.
+    tick(&mut r);
+}
+
+#[test]
+fn late_start_day_still_receives_overdue_deliveries() {
+    let mut r = rose_with(vec![]);
+    let mut supply = Supply::default();
+    supply.schedule(Delivery::new(1, vec![ItemSpec::new("Elixir", 5, 7, 1)]));
+    tick(&mut r);
+    tick(&mut r);
+    assert_eq!(supply.start_day(&mut r).received.len(), 1);
+    assert_eq!(r.items.len(), 1);
+}
+
+#[test]
+fn reorder_below_threshold_arrives_after_lead_time() {
+    let mut r = rose_with(vec![mk("Aged Brie", 2, 0)]);
+    let mut supply = supply_with("Acme", 2, "Aged Brie=10,0,3<2@Acme");
+
+    let report = supply.start_day(&mut r);
+    assert_eq!(report.ordered.len(), 1);
+    assert_eq!(report.ordered[0].day, 2);
+    assert_eq!(report.ordered[0].supplier.as_deref(), Some("Acme"));
+
+    // Still below threshold, but an order is outstanding.
+    tick(&mut r);
+    assert_eq!(supply.start_day(&mut r), Default::default());
+
+    tick(&mut r);
+    let report = supply.start_day(&mut r);
+    assert_eq!(report.received.len(), 1);
+    assert!(report.ordered.is_empty());
+    assert_eq!(r.items.len(), 4);
+}
+
+#[test]
+fn reorder_with_zero_lead_time_arrives_immediately() {
+    let mut r = rose_with(vec![]);
+    let mut supply = supply_with("Local", 0, "Elixir=5,7,2<1@Local");
+    let report = supply.start_day(&mut r);
+    assert_eq!((report.ordered.len(), report.received.len()), (1, 1));
+    assert_eq!(r.items.len(), 2);
+    assert!(supply.pending().is_empty());
+}
+
+#[test]
+fn reorder_counts_matching_names_only() {
+    let mut r = rose_with(vec![
+        mk("Aged Brie", 2, 0),
+        mk("Aged Brie", 2, 0),
+        mk("Elixir", 5, 7),
+    ]);
+    let mut supply = supply_with("Acme", 1, "Aged Brie=10,0,1<2@Acme");
+    assert!(supply.start_day(&mut r).ordered.is_empty());
+    r.items.remove(0);
+    assert_eq!(supply.start_day(&mut r).ordered.len(), 1);
+}
+
+#[test]
+fn reorder_can_count_a_whole_kind() {
+    let rule = ReorderRule::parse("Elixir=5,7,2<3/kind:normal@Acme").expect("valid rule");
+    assert_eq!(rule.selector, Selector::Kind("normal"));
+    assert_eq!(
+        ReorderRule::parse("Elixir=5,7<3/Elixir*@Acme").map(|r| r.selector),
+        Some(Selector::Name("Elixir*".into()))
+    );
+    for bad in ["Elixir=5,7<3/kind:food@Acme", "Elixir=5,7<3/@Acme"] {
+        assert_eq!(ReorderRule::parse(bad), None, "{bad:?}");
+    }
+
+    let mut r = rose_with(vec![
+        mk("+5 Dexterity Vest", 10, 20),
+        mk("Elixir", 5, 7),
+        mk("Aged Brie", 2, 0),
+    ]);
+    let mut supply = supply_with("Acme", 1, "Elixir=5,7,2<3/kind:normal@Acme");
+    // Two normal items; Aged Brie does not count.
+    assert_eq!(supply.start_day(&mut r).ordered.len(), 1);
+    tick(&mut r);
+    supply.start_day(&mut r);
+    assert_eq!(r.items.len(), 5);
+    assert!(supply.start_day(&mut r).ordered.is_empty());
+}
+
+#[test]
+fn reorder_rule_needs_a_known_supplier() {
+    let mut supply = Supply::default();
+    let rule = ReorderRule::parse("Elixir=5,7<1@Nobody").expect("valid rule");
+    assert_eq!(
+        supply.add_rule(rule),
+        Err(SupplyError::UnknownSupplier("Nobody".to_string()))
+    );
+}
+
+#[test]
+fn cli_parses_supply_flags() {
+    let args = [
+        "--supplier",
+        "Acme=2",
+        "--reorder",
+        "Aged Brie=10,0,3<2@Acme",
+        "--deliver",
+        "Elixir=5,7@4",
+    ]
+    .map(String::from);
+    let opts = cli::parse(args).expect("valid flags");
+    assert_eq!(opts.supply.pending().len(), 1);
+    assert_eq!(opts.supply.pending()[0].day, 4);
+
+    let args = ["--reorder", "Aged Brie=10,0,3<2@Acme"].map(String::from);
+    assert!(cli::parse(args).is_err_and(|e| e == CliError::Usage));
+}
+
+//
+// Monte Carlo demand
+//
+
+fn demand(pattern: &str, mean: u32) -> DemandRule {
+    DemandRule::parse(&format!("{}={}", pattern, mean)).expect("valid demand")
+}
+
+fn demo_rose() -> GildedRose {
+    rose_with(vec![
+        mk("+5 Dexterity Vest", 10, 20),
+        mk("Aged Brie", 2, 0),
+        mk("Elixir of the Mongoose", 5, 7),
+        mk("Elixir of the Mongoose", 5, 3),
+        mk("Sulfuras, Hand of Ragnaros", 0, QUALITY_SULFURAS),
+        mk(PASS_TAFKAL, 5, 49),
+    ])
+}
+
+#[test]
+fn rng_is_deterministic_per_seed() {
+    let draws = |seed| {
+        let mut rng = Rng::new(seed);
+        (0..8).map(|_| rng.next_u64()).collect::<Vec<_>>()
+    };
+    assert_eq!(draws(7), draws(7));
+    assert_ne!(draws(7), draws(8));
+    let mut rng = Rng::new(0);
+    assert!((0..1000).all(|_| rng.up_to(4) <= 4));
+    assert!((0..100).all(|_| rng.up_to(0) == 0));
+}
+
+#[test]
+fn distribution_uses_nearest_rank() {
+    let d = Distribution::of((1..=10).collect());
+    assert_eq!((d.min, d.p10, d.median, d.p90, d.max), (1, 1, 5, 9, 10));
+    assert_eq!(d.mean, 5);
+    assert_eq!(Distribution::of(vec![]), Distribution::default());
+    assert_eq!(Distribution::of(vec![3]).p90, 3);
+}
+
+#[test]
+fn identical_seeds_give_identical_reports() {
+    let mc = MonteCarlo::new(42, 20, 31, vec![demand("*", 1)]);
+    let a = mc.run(demo_rose, &Supply::default());
+    let b = mc.run(demo_rose, &Supply::default());
+    assert_eq!(a, b);
+    assert_eq!(a.trials.len(), 20);
+
+    // A single trial can be replayed on its own.
+    assert_eq!(mc.trial(3, demo_rose(), Supply::default()), a.trials[3]);
+
+    let other = MonteCarlo::new(43, 20, 31, vec![demand("*", 1)]);
+    assert_ne!(other.run(demo_rose, &Supply::default()).trials, a.trials);
+}
+
+#[test]
+fn customers_prefer_higher_quality_or_lower_price() {
+    // One day with 0..=2 customers; take the first seed that sells exactly one unit.
+    let sold_price = |preference| {
+        (0..)
+            .find_map(|seed| {
+                let mut mc = MonteCarlo::new(seed, 1, 1, vec![demand("Elixir*", 1)]);
+                mc.preference = preference;
+                let outcome = mc.trial(0, demo_rose(), Supply::default());
+                (outcome.sold == 1).then_some(outcome.revenue_cents)
+            })
+            .expect("some seed sells exactly one")
+    };
+    let pricing = Pricing::default();
+    assert_eq!(
+        sold_price(Preference::HigherQuality),
+        pricing.base_cents + 7 * pricing.per_quality_cents
+    );
+    assert_eq!(
+        sold_price(Preference::LowerPrice),
+        pricing.base_cents + 3 * pricing.per_quality_cents
+    );
+}
+
+#[test]
+fn demand_can_target_a_whole_kind() {
+    assert_eq!(
+        DemandRule::parse("kind:appreciating=2"),
+        Some(DemandRule {
+            selector: Selector::Kind("appreciating"),
+            mean: 2
+        })
+    );
+    assert_eq!(DemandRule::parse("kind:cheese=2"), None);
+    assert_eq!(DemandRule::parse("=2"), None);
+    // Only the Vest and the two Elixirs are normal, so at most 3 units sell.
+    let mc = MonteCarlo::new(7, 10, 5, vec![demand("kind:normal", 3)]);
+    let report = mc.run(demo_rose, &Supply::default());
+    assert!(report.trials.iter().all(|t| t.sold <= 3));
+    assert!(report.trials.iter().any(|t| t.sold > 0));
+}
+
+#[test]
+fn unmet_demand_counts_as_stock_outs() {
+    // Legendary items are not for sale, so every customer leaves empty-handed.
+    let mc = MonteCarlo::new(1, 5, 10, vec![demand("Sulfuras*", 3)]);
+    let report = mc.run(demo_rose, &Supply::default());
+    for t in &report.trials {
+        assert_eq!((t.sold, t.revenue_cents), (0, 0));
+    }
+    assert!(report.stock_outs.max > 0);
+}
+
+#[test]
+fn expired_worthless_items_are_wasted() {
+    let mc = MonteCarlo::new(1, 1, 12, vec![]);
+    let outcome = mc.trial(0, demo_rose(), Supply::default());
+    // Both Elixirs and the pass end expired at 0; the Vest is not expired yet, Brie keeps
+    // appreciating and Sulfuras never changes.
+    assert_eq!(
+        outcome,
+        TrialOutcome {
+            waste: 3,
+            ..TrialOutcome::default()
+        }
+    );
+}
+
+#[test]
+fn cli_parses_simulation_flags() {
+    let args = [
+        "--simulate",
+        "100@7",
+        "--demand",
+        "Aged Brie=2",
+        "--prefer",
+        "price",
+    ]
+    .map(String::from);
+    let opts = cli::parse(args).expect("valid flags");
+    assert_eq!(opts.simulate, Some((100, 7)));
+    assert_eq!(opts.demand, vec![demand("Aged Brie", 2)]);
+    assert_eq!(opts.preference, Preference::LowerPrice);
+    assert!(
+        cli::parse(["--prefer", "cheap"].map(String::from)).is_err_and(|e| e == CliError::Usage)
+    );
+}
+
+//
+// Sell-day advisor
+//
+
+fn advisor(horizon: u32) -> Advisor {
+    Advisor::new(Pricing::default(), SalePolicy::default(), horizon)
+}
+
+#[test]
+fn forecast_ages_a_copy_only() {
+    let r = rose_with(vec![mk("Aged Brie", 2, 0)]);
+    let states = r.forecast(0, 3).expect("item exists");
+    let got: Vec<(i32, i32)> = states.iter().map(|it| (it.sell_in, it.quality)).collect();
+    assert_eq!(got, vec![(2, 0), (1, 1), (0, 2), (-1, 4)]);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (2, 0));
+    assert_eq!(r.day(), 0);
+    assert!(r.forecast(1, 3).is_none());
+}
+
+#[test]
+fn advisor_sells_backstage_pass_before_the_concert() {
+    let r = rose_with(vec![mk(PASS_TAFKAL, 15, 20)]);
+    let advice = advisor(30).advise(&r, 0).expect("sellable");
+    // 20 + 5×1 + 5×2 + 5×3 = 50 on the concert day (sell_in 0); the day after it is worth 0.
+    assert_eq!((advice.day, advice.sell_in, advice.quality), (15, 0, 50));
+    assert_eq!(advice.today_cents, 100 + 20 * 50);
+}
+
+#[test]
+fn advisor_stops_at_the_cap_for_appreciating_goods() {
+    let r = rose_with(vec![mk("Aged Brie", 2, 40)]);
+    let advice = advisor(30).advise(&r, 0).expect("sellable");
+    // +1, +1, then +2 per day after the sell-by date: 41, 42, 44, 46, 48, 50.
+    assert_eq!((advice.day, advice.quality), (6, 50));
+}
+
+#[test]
+fn advisor_sells_decaying_items_today() {
+    let r = rose_with(vec![mk("+5 Dexterity Vest", 10, 20)]);
+    let advice = advisor(30).advise(&r, 0).expect("sellable");
+    assert_eq!((advice.day, advice.price_cents), (0, advice.today_cents));
+}
+
+#[test]
+fn advisor_follows_the_registered_event_date() {
+    let mut r = rose_with(vec![mk(PASS_TAFKAL, 15, 20)]);
+    r.schedule_event(EVENT_TAFKAL, 3);
+    let advice = advisor(30).advise(&r, 0).expect("sellable");
+    // 20 + 3 + 3 + 3 = 29 on concert day 3.
+    assert_eq!((advice.day, advice.quality), (3, 29));
+}
+
+#[test]
+fn advisor_skips_unsellable_items() {
+    let mut r = rose_with(vec![
+        mk("Sulfuras, Hand of Ragnaros", 0, QUALITY_SULFURAS),
+        mk("+5 Dexterity Vest", 10, 20),
+        mk("Aged Brie", 2, 0),
+    ]);
+    tick(&mut r);
+    assert!(advisor(5).advise(&r, 0).is_none());
+    let schedule = advisor(5).schedule(&r);
+    let got: Vec<(u32, usize)> = schedule.iter().map(|a| (a.day, a.index)).collect();
+    // Days are absolute: the vest goes today (day 1), Brie at the end of the horizon.
+    assert_eq!(got, vec![(1, 1), (6, 2)]);
+}
+
+#[test]
+fn cli_parses_advise() {
+    let opts = cli::parse(["--advise", "20"].map(String::from)).expect("valid flags");
+    assert_eq!(opts.advise, Some(20));
+    assert!(cli::parse(["--advise", "-1"].map(String::from)).is_err_and(|e| e == CliError::Usage));
+}
+
+//
+// Alerts
+//
+
+fn alert_rules(specs: &[&str]) -> Alerts {
+    Alerts::new(
+        specs
+            .iter()
+            .map(|s| AlertRule::parse(s).expect("valid rule"))
+            .collect(),
+    )
+}
+
+// Collects batches so tests can check what a sink was given.
+struct RecordingSink(std::rc::Rc<std::cell::RefCell<Vec<Alert>>>);
+
+impl AlertSink for RecordingSink {
+    fn deliver(&mut self, alerts: &[Alert]) -> std::io::Result<()> {
+        self.0.borrow_mut().extend_from_slice(alerts);
+        Ok(())
+    }
+}
+
+#[test]
+fn alert_rule_parse() {
+    assert_eq!(
+        AlertRule::parse("expires:3"),
+        Some(AlertRule::ExpiresWithin { days: 3 })
+    );
+    assert_eq!(
+        AlertRule::parse("quality:5"),
+        Some(AlertRule::QualityBelow { quality: 5 })
+    );
+    assert_eq!(
+        AlertRule::parse("concert:2"),
+        Some(AlertRule::ConcertWithin { days: 2 })
+    );
+    assert_eq!(AlertRule::parse("cap"), Some(AlertRule::AtCap));
+    assert_eq!(AlertRule::parse("cap:1"), None);
+    assert_eq!(AlertRule::parse("expires"), None);
+}
+
+#[test]
+fn alert_rules_fire_on_their_condition() {
+    let profile = Profile::default();
+    let normal = profile.kind_of("Elixir");
+    let pass = profile.kind_of(PASS_TAFKAL);
+    let expires = AlertRule::ExpiresWithin { days: 2 };
+    assert!(expires.fires(&normal, 2, 10));
+    assert!(expires.fires(&normal, 0, 10));
+    assert!(!expires.fires(&normal, 3, 10));
+    // Already expired is too late to warn about.
+    assert!(!expires.fires(&normal, -1, 10));
+
+    let concert = AlertRule::ConcertWithin { days: 1 };
+    assert!(concert.fires(&pass, 1, 10));
+    assert!(!concert.fires(&normal, 1, 10));
+
+    assert!(AlertRule::QualityBelow { quality: 5 }.fires(&normal, 9, 4));
+    assert!(!AlertRule::QualityBelow { quality: 5 }.fires(&normal, 9, 5));
+    assert!(AlertRule::AtCap.fires(&profile.kind_of("Aged Brie"), 9, QUALITY_MAX));
+}
+
+#[test]
+fn legendary_items_never_alert() {
+    let r = rose_with(vec![mk("Sulfuras, Hand of Ragnaros", 0, QUALITY_SULFURAS)]);
+    let alerts = alert_rules(&["expires:3", "quality:100", "cap"]);
+    assert!(alerts.evaluate(&r).is_empty());
+}
+
+#[test]
+fn alerts_are_evaluated_after_the_update() {
+    let mut r = rose_with(vec![mk("Elixir", 3, 5), mk(PASS_TAFKAL, 3, 48)]);
+    let seen = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let mut alerts = alert_rules(&["expires:2", "concert:2", "cap"]);
+    alerts.add_sink(Box::new(RecordingSink(seen.clone())));
+    tick(&mut r);
+    let (fired, errors) = alerts.check(&r);
+    assert!(errors.is_empty());
+    let got: Vec<(u32, usize, AlertRule)> =
+        fired.iter().map(|a| (a.day, a.index, a.rule)).collect();
+    assert_eq!(
+        got,
+        vec![
+            (1, 0, AlertRule::ExpiresWithin { days: 2 }),
+            (1, 1, AlertRule::ExpiresWithin { days: 2 }),
+            (1, 1, AlertRule::ConcertWithin { days: 2 }),
+            (1, 1, AlertRule::AtCap),
+        ]
+    );
+    assert_eq!(*seen.borrow(), fired);
+}
+
+#[test]
+fn writer_sink_prints_one_line_per_alert() {
+    let r = rose_with(vec![mk("Elixir", 1, 5)]);
+    let alerts = alert_rules(&["expires:1"]).evaluate(&r);
+    let mut sink = WriterSink::new(Vec::new());
+    sink.deliver(&alerts).expect("in-memory write");
+    let out = String::from_utf8(sink.into_inner()).expect("utf-8");
+    assert_eq!(out, "alert: day 0, Elixir, 1, 5: expires within 1 days\n");
+}
+
+#[test]
+fn file_sink_appends_json_lines() {
+    let path =
+        std::env::temp_dir().join(format!("gilded-rose-alerts-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let r = rose_with(vec![mk("Elixir \"special\"", 1, 5)]);
+    let alerts = alert_rules(&["expires:1"]).evaluate(&r);
+    let mut sink = FileSink::new(&path);
+    sink.deliver(&alerts).expect("writable temp dir");
+    sink.deliver(&[]).expect("nothing to write");
+    sink.deliver(&alerts).expect("writable temp dir");
+    let written = std::fs::read_to_string(&path).expect("file exists");
+    let _ = std::fs::remove_file(&path);
+    let line = r#"{"day":0,"index":0,"name":"Elixir \"special\"","sell_in":1,"quality":5,"rule":"expires within 1 days"}"#;
+    assert_eq!(written, format!("{line}\n{line}\n"));
+}
+
+#[test]
+fn webhook_sink_only_targets_loopback() {
+    assert!(WebhookSink::parse("http://127.0.0.1:8080/alerts").is_some());
+    assert!(WebhookSink::parse("http://localhost:8080").is_some());
+    assert!(WebhookSink::parse("http://10.0.0.1:8080/alerts").is_none());
+    assert!(WebhookSink::parse("https://127.0.0.1:8080/alerts").is_none());
+    assert!(WebhookSink::parse("http://127.0.0.1/alerts").is_none());
+}
+
+#[test]
+fn webhook_sink_posts_a_json_array() {
+    use std::io::{Read, Write};
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind loopback");
+    let port = listener.local_addr().expect("bound").port();
+    let server = std::thread::spawn(move || {
+        let (mut conn, _) = listener.accept().expect("one request");
+        let mut request = Vec::new();
+        let mut buf = [0; 4096];
+        // The body is small; read until the JSON array is complete.
+        while !request.ends_with(b"]") {
+            let n = conn.read(&mut buf).expect("readable");
+            if n == 0 {
+                break;
+            }
+            request.extend_from_slice(&buf[..n]);
+        }
+        conn.write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+            .expect("writable");
+        String::from_utf8(request).expect("utf-8")
+    });
+    let r = rose_with(vec![mk("Elixir", 1, 5)]);
+    let alerts = alert_rules(&["expires:1"]).evaluate(&r);
+    let mut sink =
+        WebhookSink::parse(&format!("http://127.0.0.1:{}/hook", port)).expect("valid url");
+    sink.deliver(&alerts).expect("2xx answer");
+    let request = server.join().expect("server thread");
+    assert!(request.starts_with("POST /hook HTTP/1.1\r\n"));
+    assert!(request.ends_with(&format!("\r\n\r\n[{}]", alerts[0].to_json())));
+}
+
+#[test]
+fn failing_sink_does_not_stop_the_others() {
+    // Bind and drop a listener to get a loopback port that refuses connections.
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|l| l.local_addr())
+        .expect("bind loopback")
+        .port();
+    let seen = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let mut alerts = alert_rules(&["cap"]);
+    let webhook = WebhookSink::parse(&format!("http://127.0.0.1:{}/", port)).expect("valid url");
+    alerts.add_sink(Box::new(webhook));
+    alerts.add_sink(Box::new(RecordingSink(seen.clone())));
+    let r = rose_with(vec![mk("Aged Brie", 2, QUALITY_MAX)]);
+    let (fired, errors) = alerts.check(&r);
+    assert_eq!((fired.len(), errors.len()), (1, 1));
+    assert_eq!(seen.borrow().len(), 1);
+}
+
+#[test]
+fn cli_parses_alert_flags() {
+    let args = [
+        "--alert",
+        "expires:3",
+        "--alert",
+        "cap",
+        "--alert-file",
+        "alerts.jsonl",
+        "--alert-webhook",
+        "http://127.0.0.1:9000/hook",
+    ]
+    .map(String::from);
+    let opts = cli::parse(args).expect("valid flags");
+    assert_eq!(
+        opts.alerts,
+        vec![AlertRule::ExpiresWithin { days: 3 }, AlertRule::AtCap]
+    );
+    assert_eq!(opts.alert_file.as_deref(), Some("alerts.jsonl"));
+    assert!(opts.alert_webhook.is_some());
+    let args = ["--alert-webhook", "http://example.com:80/hook"].map(String::from);
+    assert!(cli::parse(args).is_err_and(|e| e == CliError::Usage));
+}
+
+//
+// Observers
+//
+
+type Log = std::rc::Rc<std::cell::RefCell<Vec<String>>>;
+
+// Records every callback as "callback day index quality".
+struct LogObserver(Log);
+
+impl LogObserver {
+    fn push(&self, what: &str, day: u32, index: usize, it: &Item) {
+        self.0
+            .borrow_mut()
+            .push(format!("{} {} {} {}", what, day, index, it.quality));
+    }
+}
+
+impl Observer for LogObserver {
+    fn before_update(&mut self, day: u32, index: usize, it: &Item) {
+        self.push("before", day, index, it);
+    }
+
+    fn after_update(&mut self, day: u32, index: usize, it: &Item) {
+        self.push("after", day, index, it);
+    }
+
+    fn expired(&mut self, day: u32, index: usize, it: &Item) {
+        self.push("expired", day, index, it);
+    }
+
+    fn clamped(&mut self, day: u32, index: usize, it: &Item, bound: Bound) {
+        let what = match bound {
+            Bound::Cap => "cap",
+            Bound::Floor => "floor",
+        };
+        self.push(what, day, index, it);
+    }
+
+    fn concert_passed(&mut self, day: u32, index: usize, it: &Item) {
+        self.push("concert", day, index, it);
+    }
+}
+
+fn observed(items: Vec<Item>) -> (GildedRose, Log) {
+    let log = Log::default();
+    let mut r = rose_with(items);
+    r.add_observer(Box::new(LogObserver(log.clone())));
+    (r, log)
+}
+
+#[test]
+fn observer_sees_before_and_after_each_item() {
+    let (mut r, log) = observed(vec![mk("Elixir", 5, 7), mk("Aged Brie", 5, 7)]);
+    tick(&mut r);
+    assert_eq!(
+        *log.borrow(),
+        ["before 0 0 7", "after 0 0 6", "before 0 1 7", "after 0 1 8"]
+    );
+}
+
+#[test]
+fn observer_sees_expiry_once() {
+    let (mut r, log) = observed(vec![mk("Elixir", 0, 10)]);
+    tick(&mut r);
+    tick(&mut r);
+    assert_eq!(
+        *log.borrow(),
+        [
+            "before 0 0 10",
+            "expired 0 0 8",
+            "after 0 0 8",
+            "before 1 0 8",
+            "after 1 0 6"
+        ]
+    );
+}
+
+#[test]
+fn observer_sees_clamping_at_cap_and_floor() {
+    let (mut r, log) = observed(vec![mk("Aged Brie", 0, 49), mk("Elixir", 3, 0)]);
+    tick(&mut r);
+    // Brie: +1 to 50, expires, then the expiry +1 is cut off by the cap. Elixir at 0 hits the
+    // floor. Callbacks run once the item is updated, so they all see the final quality.
+    assert_eq!(
+        *log.borrow(),
+        [
+            "before 0 0 49",
+            "expired 0 0 50",
+            "cap 0 0 50",
+            "after 0 0 50",
+            "before 0 1 0",
+            "floor 0 1 0",
+            "after 0 1 0"
+        ]
+    );
+}
+
+#[test]
+fn observer_sees_expiry_of_a_cancelled_pass() {
+    let (mut r, log) = observed(vec![mk(PASS_TAFKAL, 5, 30)]);
+    r.schedule_event(EVENT_TAFKAL, 1);
+    r.cancel_event(EVENT_TAFKAL).expect("scheduled event");
+    tick(&mut r);
+    tick(&mut r);
+    // No concert took place, so only the expiry is reported, once.
+    assert_eq!(
+        *log.borrow(),
+        [
+            "before 0 0 0",
+            "after 0 0 0",
+            "before 1 0 0",
+            "expired 1 0 0",
+            "after 1 0 0"
+        ]
+    );
+}
+
+#[test]
+fn observer_sees_concert_passed() {
+    let (mut r, log) = observed(vec![mk(PASS_TAFKAL, 0, 30)]);
+    tick(&mut r);
+    assert_eq!(
+        *log.borrow(),
+        [
+            "before 0 0 30",
+            "expired 0 0 0",
+            "concert 0 0 0",
+            "after 0 0 0"
+        ]
+    );
+}
+
+#[test]
+fn observer_sees_legendary_items_unchanged() {
+    let (mut r, log) = observed(vec![mk("Sulfuras, Hand of Ragnaros", -1, QUALITY_SULFURAS)]);
+    tick(&mut r);
+    assert_eq!(*log.borrow(), ["before 0 0 80", "after 0 0 80"]);
+}
+
+#[test]
+fn observers_do_not_change_the_rules() {
+    let items = || {
+        vec![
+            mk("+5 Dexterity Vest", 10, 20),
+            mk("Aged Brie", 2, 0),
+            mk("Conjured Mana Cake", 3, 6),
+            mk(PASS_TAFKAL, 5, 49),
+        ]
+    };
+    let mut plain = rose_with(items());
+    let (mut watched, _) = observed(items());
+    for _ in 0..20 {
+        tick(&mut plain);
+        tick(&mut watched);
+    }
+    let state = |r: &GildedRose| -> Vec<(i32, i32)> {
+        r.items.iter().map(|it| (it.sell_in, it.quality)).collect()
+    };
+    assert_eq!(state(&plain), state(&watched));
+}
+
+//
+// Custom item behaviors
+//
+
+// Gains 1 a day (scaled by modifiers), 3 after the sell-by date.
+struct FineWine;
+
+impl ItemBehavior for FineWine {
+    fn classify(&self, base_name: &str) -> bool {
+        base_name.starts_with("Fine Wine")
+    }
+
+    fn daily(&self, ctx: &ItemContext<'_>, _sell_in: i32, quality: i32) -> i32 {
+        quality + ctx.mods.appreciate(1)
+    }
+
+    fn expiry(&self, ctx: &ItemContext<'_>, _sell_in: i32, quality: i32) -> i32 {
+        quality + ctx.mods.appreciate(2)
+    }
+}
+
+// Never ages and keeps whatever it is given; claims everything.
+struct Frozen;
+
+impl ItemBehavior for Frozen {
+    fn classify(&self, _base_name: &str) -> bool {
+        true
+    }
+
+    fn daily(&self, _ctx: &ItemContext<'_>, _sell_in: i32, quality: i32) -> i32 {
+        quality
+    }
+
+    fn next_sell_in(&self, _ctx: &ItemContext<'_>, sell_in: i32) -> i32 {
+        sell_in
+    }
+
+    fn validate(&self, _ctx: &ItemContext<'_>, sell_in: i32, _quality: i32) -> Result<(), String> {
+        if sell_in < 0 {
+            return Err("frozen goods cannot be expired".to_string());
+        }
+        Ok(())
+    }
+}
+
+// A custom legendary kind: held at 80, never sold.
+struct Relic;
+
+impl ItemBehavior for Relic {
+    fn classify(&self, base_name: &str) -> bool {
+        base_name == "Relic"
+    }
+
+    fn daily(&self, _ctx: &ItemContext<'_>, _sell_in: i32, quality: i32) -> i32 {
+        quality
+    }
+
+    fn next_sell_in(&self, _ctx: &ItemContext<'_>, sell_in: i32) -> i32 {
+        sell_in
+    }
+
+    fn quality_bounds(&self, _ctx: &ItemContext<'_>) -> RangeInclusive<i32> {
+        80..=80
+    }
+}
+
+#[test]
+fn custom_behavior_can_hold_quality_above_the_cap() {
+    let (mut r, log) = observed(vec![mk("Relic", 5, 80)]);
+    r.add_behavior(Box::new(Relic));
+    tick(&mut r);
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (5, 80));
+    assert_eq!(r.validate(), []);
+    assert!(!log.borrow().iter().any(|l| l.starts_with("cap")));
+}
+
+#[test]
+fn custom_behavior_runs_daily_then_expiry() {
+    let mut r = rose_with(vec![mk("Fine Wine 1990", 1, 10), mk("Elixir", 1, 10)]);
+    r.add_behavior(Box::new(FineWine));
+    tick(&mut r);
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (-1, 14));
+    // Built-in kinds are untouched by the chain.
+    assert_eq!((r.items[1].sell_in, r.items[1].quality), (-1, 7));
+}
+
+#[test]
+fn custom_behavior_sees_modifiers() {
+    let mut r = rose_with_modifiers(vec![mk("Enchanted Fine Wine", 5, 10)]);
+    r.add_behavior(Box::new(FineWine));
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 12);
+}
+
+#[test]
+fn custom_behavior_quality_is_clamped_and_reported() {
+    let (mut r, log) = observed(vec![mk("Fine Wine", 0, 49)]);
+    r.add_behavior(Box::new(FineWine));
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, QUALITY_MAX);
+    assert_eq!(
+        *log.borrow(),
+        [
+            "before 0 0 49",
+            "expired 0 0 50",
+            "cap 0 0 50",
+            "after 0 0 50"
+        ]
+    );
+}
+
+#[test]
+fn behavior_chain_goes_first_and_in_order() {
+    let mut r = rose_with(vec![
+        mk("Fine Wine", 5, 10),
+        mk("Sulfuras, Hand of Ragnaros", 5, 10),
+    ]);
+    r.add_behavior(Box::new(FineWine));
+    r.add_behavior(Box::new(Frozen));
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (4, 11));
+    // Frozen claims Sulfuras ahead of the legendary registry, so quality 10 is fine.
+    assert_eq!((r.items[1].sell_in, r.items[1].quality), (5, 10));
+}
+
+#[test]
+fn forecast_uses_custom_behaviors() {
+    let mut r = rose_with(vec![mk("Fine Wine", 1, 10)]);
+    r.add_behavior(Box::new(FineWine));
+    let states = r.forecast(0, 2).expect("item exists");
+    assert_eq!(states[2].quality, 14);
+}
+
+#[test]
+fn validate_reports_broken_preconditions() {
+    let mut r = rose_with(vec![
+        mk("Elixir", 5, 51),
+        mk("Sulfuras, Hand of Ragnaros", 0, 79),
+        mk("Aged Brie", 5, 50),
+        mk("Fine Wine", -1, 10),
+    ]);
+    assert_eq!(
+        r.validate(),
+        vec![
+            (0, "quality out of range: 51".to_string()),
+            (1, "Legendary must have quality 80, got 79".to_string())
+        ]
+    );
+    r.add_behavior(Box::new(Frozen));
+    let invalid: Vec<usize> = r.validate().into_iter().map(|(i, _)| i).collect();
+    assert_eq!(invalid, vec![3]);
+}
+
+//
+// Expression rules
+//
+
+fn eval(src: &str, env: Env) -> Result<i64, EvalError> {
+    Expr::parse(src).expect("valid expression").eval(&env)
+}
+
+fn env(sell_in: i64, quality: i64) -> Env {
+    Env {
+        sell_in,
+        quality,
+        ..Env::default()
+    }
+}
+
+#[test]
+fn expr_arithmetic_and_precedence() {
+    let e = Env::default();
+    assert_eq!(eval("1 + 2 * 3", e), Ok(7));
+    assert_eq!(eval("(1 + 2) * 3", e), Ok(9));
+    assert_eq!(eval("10 - 4 - 3", e), Ok(3));
+    assert_eq!(eval("-7 / 2", e), Ok(-3));
+    assert_eq!(eval("-7 % 3", e), Ok(-1));
+    assert_eq!(eval("--5", e), Ok(5));
+    assert_eq!(eval("min(3, -2) + max(1, 4)", e), Ok(2));
+}
+
+#[test]
+fn expr_comparisons_and_logic() {
+    let e = env(2, 10);
+    assert_eq!(eval("sell_in < 3", e), Ok(1));
+    assert_eq!(eval("sell_in >= 3", e), Ok(0));
+    assert_eq!(eval("sell_in < 3 && quality > 5", e), Ok(1));
+    assert_eq!(eval("sell_in > 3 || quality == 10", e), Ok(1));
+    assert_eq!(eval("!(quality != 10)", e), Ok(1));
+    assert_eq!(eval("1 + 1 < 3", e), Ok(1));
+}
+
+#[test]
+fn expr_conditionals_and_variables() {
+    let rule = "if sell_in < 3 then -3 else -1";
+    assert_eq!(eval(rule, env(2, 10)), Ok(-3));
+    assert_eq!(eval(rule, env(3, 10)), Ok(-1));
+    let e = Env {
+        conjured: 1,
+        day: 7,
+        ..Env::default()
+    };
+    assert_eq!(
+        eval("if conjured then -2 else if day > 5 then 1 else 0", e),
+        Ok(-2)
+    );
+    assert_eq!(eval("day * 2", e), Ok(14));
+}
+
+#[test]
+fn expr_errors_instead_of_panicking() {
+    let e = env(0, i64::MAX);
+    assert_eq!(eval("quality + 1", e), Err(EvalError::Overflow));
+    assert_eq!(eval("quality * 2", e), Err(EvalError::Overflow));
+    assert_eq!(eval("-quality - 2", e), Err(EvalError::Overflow));
+    assert_eq!(eval("(-quality - 1) / -1", e), Err(EvalError::Overflow));
+    assert_eq!(eval("1 / sell_in", e), Err(EvalError::DivisionByZero));
+    assert_eq!(eval("1 % sell_in", e), Err(EvalError::DivisionByZero));
+    // `&&` short-circuits, so the guarded division never runs.
+    assert_eq!(eval("sell_in != 0 && 10 / sell_in > 2", e), Ok(0));
+}
+
+#[test]
+fn expr_parse_errors() {
+    assert_eq!(
+        Expr::parse("quality + $"),
+        Err(ParseError::UnexpectedChar {
+            offset: 10,
+            ch: '$'
+        })
+    );
+    assert_eq!(
+        Expr::parse("price * 2"),
+        Err(ParseError::UnknownVariable {
+            offset: 0,
+            name: "price".to_string()
+        })
+    );
+    assert_eq!(Expr::parse("1 +"), Err(ParseError::UnexpectedEnd));
+    assert_eq!(
+        Expr::parse("1 2"),
+        Err(ParseError::UnexpectedToken {
+            offset: 2,
+            found: "2".to_string()
+        })
+    );
+    assert_eq!(Expr::parse("if 1 then 2"), Err(ParseError::UnexpectedEnd));
+    assert_eq!(
+        Expr::parse("99999999999999999999"),
+        Err(ParseError::NumberTooLarge { offset: 0 })
+    );
+    let deep = format!("{}1{}", "(".repeat(100), ")".repeat(100));
+    assert_eq!(Expr::parse(&deep), Err(ParseError::TooDeep));
+    let long = vec!["1"; 1000].join(" + ");
+    assert_eq!(Expr::parse(&long), Err(ParseError::TooLong));
+}
+
+#[test]
+fn expr_rule_parse() {
+    let rule = ExprRule::parse("Mana*=if sell_in < 3 then -3 else -1;-2").expect("valid rule");
+    assert_eq!(rule.pattern, "Mana*");
+    assert_eq!(rule.expiry, Expr::parse("-2").ok());
+    assert!(ExprRule::parse("Mana=").is_err());
+    assert_eq!(ExprRule::parse("Mana"), Err(ParseError::MissingAssignment));
+}
+
+#[test]
+fn expr_rule_errors_point_into_the_spec() {
+    let e = ExprRule::parse("Mana*=-2 $").expect_err("bad daily");
+    assert_eq!(e, ParseError::UnexpectedChar { offset: 9, ch: '$' });
+    assert_eq!(e.to_string(), "unexpected character '$' at 9");
+    let e = ExprRule::parse("Mana*=-1;sell_in + foo").expect_err("bad expiry");
+    assert_eq!(
+        e,
+        ParseError::UnknownVariable {
+            offset: 19,
+            name: "foo".into()
+        }
+    );
+}
+
+#[test]
+fn rule_set_drives_matching_items() {
+    let rules = RuleSet::new(vec![
+        ExprRule::parse("Mana*=if sell_in < 3 then -3 else -1;-2").expect("valid rule"),
+    ]);
+    let mut r = rose_with(vec![mk("Mana Cake", 4, 20), mk("Elixir", 4, 20)]);
+    r.add_behavior(Box::new(rules));
+    let mut qualities = Vec::new();
+    for _ in 0..6 {
+        tick(&mut r);
+        qualities.push(r.items[0].quality);
+    }
+    // sell_in 4, 3: -1; 2, 1: -3; from 0 on -3 and, once expired, another -2.
+    assert_eq!(qualities, vec![19, 18, 15, 12, 7, 2]);
+    assert_eq!(r.items[1].quality, 12);
+}
+
+#[test]
+fn rule_set_reads_conjured_and_day() {
+    let rules = RuleSet::new(vec![
+        ExprRule::parse("Mana Cake=-(conjured + 1) * (day + 1)").expect("valid rule"),
+    ]);
+    let mut r = rose_with(vec![
+        mk("Conjured Mana Cake", 10, 40),
+        mk("Mana Cake", 10, 40),
+    ]);
+    r.add_behavior(Box::new(rules));
+    tick(&mut r);
+    tick(&mut r);
+    // Day 0: -2 / -1, day 1: -4 / -2. Modifiers are not applied on top.
+    assert_eq!((r.items[0].quality, r.items[1].quality), (34, 37));
+}
+
+#[test]
+fn rule_set_failed_evaluation_leaves_quality_alone() {
+    let rules = RuleSet::new(vec![
+        ExprRule::parse("Mana Cake=10 / (sell_in - 9)").expect("valid rule"),
+    ]);
+    let mut r = rose_with(vec![mk("Mana Cake", 9, 20)]);
+    r.add_behavior(Box::new(rules));
+    tick(&mut r);
+    assert_eq!((r.items[0].sell_in, r.items[0].quality), (8, 20));
+    tick(&mut r);
+    assert_eq!(r.items[0].quality, 10);
+}
+
+#[test]
+fn cli_parses_rules() {
+    let opts = cli::parse(["--rule", "Mana*=-2;-2"].map(String::from)).expect("valid flags");
+    assert_eq!(opts.rules.len(), 1);
+    let e = cli::parse(["--rule", "Mana*=-2 +"].map(String::from)).expect_err("bad rule");
+    assert_eq!(
+        e,
+        CliError::Rule {
+            spec: "Mana*=-2 +".into(),
+            error: ParseError::UnexpectedEnd
+        }
+    );
+    assert_eq!(
+        e.to_string(),
+        r#"rule "Mana*=-2 +": unexpected end of expression"#
+    );
+    let e = cli::parse(["--rule", "Mana*=-2 $"].map(String::from)).expect_err("bad rule");
+    assert_eq!(
+        e.to_string(),
+        r#"rule "Mana*=-2 $": unexpected character '$' at 9"#
+    );
+}
+
+#[test]
+fn cli_profile_must_come_before_its_overrides() {
+    let args = ["--profile", "classic", "--conjured", "all-changes"];
+    let profile = cli::parse(args.map(String::from))
+        .expect("valid flags")
+        .profile
+        .expect("profile");
+    assert_eq!(profile.conjured, ConjuredPolicy::AllChanges);
+    assert_eq!(profile.sell_in, Profile::classic_kata().sell_in);
+    for args in [
+        ["--conjured", "all-changes", "--profile", "classic"],
+        ["--legendary", "Ashbringer=80", "--profile", "default"],
+        ["--profile", "classic", "--profile", "default"],
+    ] {
+        assert!(
+            cli::parse(args.map(String::from)).is_err_and(|e| e == CliError::Usage),
+            "{args:?}"
+        );
+    }
+}
+
+#[test]
+fn cli_parses_modifier() {
+    let args = [
+        "--modifier",
+        "cursed",
+        "--modifier",
+        "blessed",
+        "--modifier",
+        "cursed",
+    ];
+    let opts = cli::parse(args.map(String::from)).expect("valid flags");
+    let prefixes: Vec<&str> = opts
+        .profile
+        .as_ref()
+        .expect("profile")
+        .modifiers
+        .iter()
+        .map(|m| m.prefix.as_ref())
+        .collect();
+    assert_eq!(prefixes, ["Cursed ", "Blessed "]);
+    assert!(
+        cli::parse(["--modifier", "Blessed"].map(String::from))
+            .is_err_and(|e| e == CliError::Usage)
+    );
+}
+
+//
+// JSON
+//
+
+#[test]
+fn json_round_trips_nested_values() {
+    let src = r#"{"name":"Aged Brie","n":[1,-2,3.5,true,null],"o":{}}"#;
+    let json = Json::parse(src).expect("valid JSON");
+    assert_eq!(json.get("name").and_then(Json::as_str), Some("Aged Brie"));
+    assert_eq!(
+        json.get("n").and_then(Json::as_array).map(<[Json]>::len),
+        Some(5)
+    );
+    assert_eq!(json.to_string(), src);
+}
+
+#[test]
+fn json_parses_escapes_and_whitespace() {
+    let json = Json::parse(" [ \"a\\\"b\\n\\u00e9\\ud83d\\ude00\" ] ").expect("valid JSON");
+    assert_eq!(json, Json::Array(vec!["a\"b\né😀".into()]));
+    assert_eq!(Json::from("tab\there").to_string(), r#""tab\there""#);
+}
+
+#[test]
+fn json_rejects_malformed_input() {
+    assert_eq!(Json::parse("[1,]"), Err(JsonError::Syntax(3)));
+    assert_eq!(Json::parse("01"), Err(JsonError::Syntax(1)));
+    assert_eq!(Json::parse("{\"a\" 1}"), Err(JsonError::Syntax(5)));
+    assert_eq!(Json::parse("[] x"), Err(JsonError::Syntax(3)));
+    assert_eq!(Json::parse(&"[".repeat(100)), Err(JsonError::TooDeep));
+}
+
+//
+// Explanations
+//
+
+#[test]
+fn explain_normal_item_past_sell_by() {
+    let r = rose_with(vec![mk("Conjured Mana Cake", 0, 10)]);
+    let e = Explanation::of(&r, 0).expect("item 0");
+    assert_eq!((e.kind, e.base_name.as_str()), ("normal", "Mana Cake"));
+    assert_eq!((e.next_sell_in, e.next_quality), (-1, 6));
+    assert_eq!(
+        e.reasons,
+        [
+            "normal: loses 2 per day",
+            "past the sell-by date: degrades twice as fast",
+            "conjured x1"
+        ]
+    );
+}
+
+#[test]
+fn explain_backstage_pass_and_cap() {
+    let r = rose_with(vec![
+        mk(PASS_TAFKAL, 5, 49),
+        mk("Sulfuras, Hand of Ragnaros", 0, 80),
+    ]);
+    let e = Explanation::of(&r, 0).expect("item 0");
+    assert_eq!((e.kind, e.next_quality), ("backstage", QUALITY_MAX));
+    assert_eq!(
+        e.reasons,
+        [
+            "backstage: gains 3 with 5 days left",
+            "quality reaches the cap of 50"
+        ]
+    );
+    let e = Explanation::of(&r, 1).expect("item 1");
+    assert_eq!((e.kind, e.next_quality), ("legendary", 80));
+    assert!(Explanation::of(&r, 2).is_none());
+}
+
+#[test]
+fn explain_expired_plateau_item_does_not_gain() {
+    let profile =
+        profile_with_appreciating("Cheddar", Appreciation::parse("2:plateau").expect("rule"));
+    let r = rose_with_profile(vec![mk("Cheddar", -3, 20)], profile);
+    let e = Explanation::of(&r, 0).expect("item 0");
+    assert_eq!((e.next_sell_in, e.next_quality), (-4, 20));
+    assert_eq!(
+        e.reasons,
+        [
+            "appreciating: stopped gaining at the sell-by date",
+            "past the sell-by date: quality holds"
+        ]
+    );
+}
+
+#[test]
+fn explain_expired_spoil_item_only_loses() {
+    let profile =
+        profile_with_appreciating("Milk", Appreciation::parse("1:spoil:3").expect("rule"));
+    let r = rose_with_profile(vec![mk("Milk", 0, 20)], profile);
+    let e = Explanation::of(&r, 0).expect("item 0");
+    assert_eq!((e.next_sell_in, e.next_quality), (-1, 17));
+    assert_eq!(
+        e.reasons,
+        [
+            "appreciating: stopped gaining at the sell-by date",
+            "past the sell-by date: loses 3 per day"
+        ]
+    );
+}
+
+//
+// HTTP service
+//
+
+fn shop() -> GildedRose {
+    rose_with(vec![
+        mk("+5 Dexterity Vest", 10, 20),
+        mk("Aged Brie", 2, 0),
+        mk(PASS_TAFKAL, 15, 20),
+    ])
+}
+
+fn service() -> Service {
+    Service::new(shop())
+}
+
+fn call(service: &mut Service, method: &str, target: &str, body: &str) -> (u16, Json) {
+    let response = service.handle(&Request::new(method, target, body));
+    (response.status, response.body)
+}
+
+#[test]
+fn service_lists_and_filters_items() {
+    let mut s = service();
+    let (status, all) = call(&mut s, "GET", "/items", "");
+    assert_eq!((status, all.as_array().map(<[Json]>::len)), (200, Some(3)));
+    let (_, brie) = call(&mut s, "GET", "/items?name=Aged+*", "");
+    assert_eq!(
+        brie.to_string(),
+        r#"[{"index":1,"name":"Aged Brie","sell_in":2,"quality":0,"kind":"appreciating"}]"#
+    );
+    let (_, passes) = call(&mut s, "GET", "/items?kind=backstage", "");
+    assert_eq!(passes.as_array().map(<[Json]>::len), Some(1));
+    let (status, one) = call(&mut s, "GET", "/items/2", "");
+    assert_eq!(
+        (status, one.get("index").and_then(Json::as_i64)),
+        (200, Some(2))
+    );
+}
+
+#[test]
+fn service_adds_and_removes_items() {
+    let mut s = service();
+    let (status, added) = call(
+        &mut s,
+        "POST",
+        "/items",
+        r#"{"name":"Elixir","sell_in":5,"quality":7}"#,
+    );
+    assert_eq!(
+        (status, added.get("index").and_then(Json::as_i64)),
+        (201, Some(3))
+    );
+    let (status, removed) = call(&mut s, "DELETE", "/items/0", "");
+    assert_eq!(status, 200);
+    assert_eq!(
+        removed.get("name").and_then(Json::as_str),
+        Some("+5 Dexterity Vest")
+    );
+    assert_eq!(s.rose().items.len(), 3);
+    assert_eq!(s.rose().items[2].name, "Elixir");
+}
+
+#[test]
+fn service_status_codes() {
+    let mut s = service();
+    let post = |s: &mut Service, body| call(s, "POST", "/items", body).0;
+    assert_eq!(post(&mut s, "{"), 400);
+    assert_eq!(post(&mut s, r#"{"name":"Elixir","sell_in":5}"#), 400);
+    assert_eq!(
+        post(&mut s, r#"{"name":"Elixir","sell_in":5,"quality":51}"#),
+        422
+    );
+    assert_eq!(
+        post(
+            &mut s,
+            r#"{"name":"Sulfuras, Hand of Ragnaros","sell_in":0,"quality":50}"#
+        ),
+        422
+    );
+    assert_eq!(call(&mut s, "GET", "/items/3", "").0, 404);
+    assert_eq!(call(&mut s, "DELETE", "/items/x", "").0, 404);
+    assert_eq!(call(&mut s, "GET", "/nowhere", "").0, 404);
+    assert_eq!(call(&mut s, "GET", "/report/1", "").0, 404);
+    assert_eq!(call(&mut s, "GET", "/tick", "").0, 405);
+    assert_eq!(call(&mut s, "PUT", "/items", "").0, 405);
+    assert_eq!(s.rose().items.len(), 3);
+}
+
+#[test]
+fn service_ticks_and_keeps_daily_reports() {
+    let mut s = service();
+    let (status, today) = call(&mut s, "POST", "/tick", "");
+    assert_eq!(
+        (status, today.get("day").and_then(Json::as_i64)),
+        (200, Some(1))
+    );
+    call(&mut s, "DELETE", "/items/1", "");
+    let (_, day0) = call(&mut s, "GET", "/report/0", "");
+    assert_eq!(
+        day0.get("totals").map(Json::to_string).as_deref(),
+        Some(r#"{"items":3,"quality":40,"expired":0}"#)
+    );
+    let (_, day1) = call(&mut s, "GET", "/report/1", "");
+    assert_eq!(
+        day1.get("totals").map(Json::to_string).as_deref(),
+        Some(r#"{"items":2,"quality":40,"expired":0}"#)
+    );
+}
+
+#[test]
+fn service_explains_items() {
+    let mut s = service();
+    let (status, e) = call(&mut s, "GET", "/items/1/explain", "");
+    assert_eq!(status, 200);
+    assert_eq!(e.get("kind").and_then(Json::as_str), Some("appreciating"));
+    assert_eq!(e.get("next_quality").and_then(Json::as_i64), Some(1));
+}
+
+// Send raw requests, one connection each, to a server on its own thread; returns the raw
+// responses.
+fn http_exchange(requests: Vec<String>) -> Vec<String> {
+    use std::io::{Read, Write};
+    let (tx, rx) = std::sync::mpsc::channel();
+    let count = requests.len();
+    // `GildedRose` is not `Send`, so the server is built on its thread.
+    let server = std::thread::spawn(move || {
+        let mut server = Server::bind("127.0.0.1:0", shop()).expect("bind loopback");
+        tx.send(server.local_addr().expect("bound"))
+            .expect("test alive");
+        for _ in 0..count {
+            server.handle_one().expect("accept");
+        }
+    });
+    let addr = rx.recv().expect("server started");
+    let responses = requests
+        .iter()
+        .map(|req| {
+            let mut conn = std::net::TcpStream::connect(addr).expect("connect");
+            conn.write_all(req.as_bytes()).expect("writable");
+            let mut response = String::new();
+            conn.read_to_string(&mut response).expect("readable");
+            response
+        })
+        .collect();
+    server.join().expect("server thread");
+    responses
+}
+
+#[test]
+fn server_answers_over_tcp() {
+    let body = r#"{"name":"Elixir","sell_in":5,"quality":7}"#;
+    let responses = http_exchange(vec![
+        format!(
+            "POST /items HTTP/1.1\r\nHost: x\r\ncontent-length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        ),
+        "GET /items?name=Elixir HTTP/1.1\r\n\r\n".to_string(),
+        "POST /tick HTTP/1.1\r\nContent-Length: 0\r\n\r\n".to_string(),
+        "DELETE /tick HTTP/1.1\r\n\r\n".to_string(),
+    ]);
+    assert!(responses[0].starts_with("HTTP/1.1 201 Created\r\n"));
+    assert!(responses[1].ends_with(
+        "\r\n\r\n[{\"index\":3,\"name\":\"Elixir\",\"sell_in\":5,\"quality\":7,\"kind\":\"normal\"}]"
+    ));
+    assert!(responses[2].starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(responses[2].contains("\"name\":\"Elixir\",\"sell_in\":4,\"quality\":6"));
+    assert!(responses[3].starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    assert!(responses[3].contains("\r\nAllow: POST\r\n"));
+}
+
+#[test]
+fn server_refuses_oversized_and_malformed_requests() {
+    let responses = http_exchange(vec![
+        "POST /items HTTP/1.1\r\nContent-Length: 1000000\r\n\r\n".to_string(),
+        "nonsense\r\n\r\n".to_string(),
+        format!(
+            "GET /items HTTP/1.1\r\nX-Pad: {}\r\n\r\n",
+            "a".repeat(10_000)
+        ),
+    ]);
+    assert!(responses[0].starts_with("HTTP/1.1 413 "));
+    assert!(responses[1].starts_with("HTTP/1.1 400 "));
+    assert!(responses[2].starts_with("HTTP/1.1 431 "));
+}
+
+#[test]
+fn cli_parses_serve() {
+    let opts = cli::parse(["--serve", "127.0.0.1:8080"].map(String::from)).expect("valid flags");
+    assert_eq!(opts.serve.as_deref(), Some("127.0.0.1:8080"));
+}
+
+//
+// JSON-RPC
+//
+
+fn session() -> Session<fn(Vec<Item>) -> GildedRose> {
+    Session::new(GildedRose::new)
+}
+
+#[test]
+fn rpc_session_transcript() {
+    let transcript = include_str!("../transcripts/rpc_session.txt");
+    assert_eq!(replay(&mut session(), transcript), Ok(9));
+}
+
+#[test]
+fn rpc_errors_transcript() {
+    let transcript = include_str!("../transcripts/rpc_errors.txt");
+    assert_eq!(replay(&mut session(), transcript), Ok(10));
+}
+
+#[test]
+fn rpc_replay_reports_mismatches() {
+    let transcript = "-> {\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"validate\"}\n\
+                      <- {\"jsonrpc\":\"2.0\",\"id\":1,\"result\":[1]}\n";
+    let err = replay(&mut session(), transcript).expect_err("wrong result");
+    assert!(err.starts_with("line 2: expected"));
+    assert!(replay(&mut session(), "<- {}").is_err());
+    assert!(replay(&mut session(), "tick").is_err());
+}
+
+#[test]
+fn rpc_invalid_load_keeps_the_inventory() {
+    let mut s = session();
+    let load = |items: &str| {
+        format!(
+            "{{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"load\",\"params\":{{\"items\":{}}}}}",
+            items
+        )
+    };
+    s.handle_line(&load(r#"[{"name":"Elixir","sell_in":5,"quality":7}]"#));
+    let answer = s
+        .handle_line(&load(r#"[{"name":"Elixir","sell_in":5,"quality":-1}]"#))
+        .expect("answered");
+    assert!(answer.contains("\"code\":-32002"));
+    assert_eq!(s.rose().items[0].quality, 7);
+}
+
+#[test]
+fn rpc_error_codes_are_stable() {
+    let codes = [
+        RpcError::Parse,
+        RpcError::InvalidRequest,
+        RpcError::MethodNotFound(String::new()),
+        RpcError::InvalidParams(String::new()),
+        RpcError::NoSuchItem(0),
+        RpcError::InvalidItems(Vec::new()),
+    ]
+    .map(|e| e.code());
+    assert_eq!(codes, [-32700, -32600, -32601, -32602, -32001, -32002]);
+}
+
+#[test]
+fn rpc_run_answers_line_by_line() {
+    let input = "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"tick\"}\n\n\
+                 {\"jsonrpc\":\"2.0\",\"method\":\"tick\"}\n\
+                 {\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"tick\"}\n";
+    let mut out = Vec::new();
+    session()
+        .run(input.as_bytes(), &mut out)
+        .expect("in-memory io");
+    let out = String::from_utf8(out).expect("utf-8");
+    assert_eq!(out.lines().count(), 2);
+    assert!(out.ends_with("\"result\":{\"day\":3,\"items\":[]}}\n"));
+}
+
+#[test]
+fn cli_parses_rpc() {
+    let opts = cli::parse(["--rpc", "stdio"].map(String::from)).expect("valid flags");
+    assert!(opts.rpc);
+    assert!(cli::parse(["--rpc", "tcp"].map(String::from)).is_err_and(|e| e == CliError::Usage));
+}
+
+//
+// Console
+//
+
+fn console() -> Console<fn(Vec<Item>) -> GildedRose> {
+    Console::new(
+        GildedRose::new,
+        vec![
+            mk("+5 Dexterity Vest", 10, 20),
+            mk("Aged Brie", 2, 0),
+            mk(PASS_TAFKAL, 15, 20),
+        ],
+    )
+}
+
+#[test]
+fn console_adds_edits_and_removes_items() {
+    let mut c = console();
+    assert_eq!(
+        c.execute("add Elixir=5,7,2"),
+        Ok("added Elixir, 5, 7 x 2".into())
+    );
+    assert_eq!(
+        c.execute("edit 3 quality=9"),
+        Ok("  3  Elixir, 5, 9".into())
+    );
+    assert_eq!(
+        c.execute("edit Aged Brie name=Conjured Aged Brie"),
+        Ok("  1  Conjured Aged Brie, 2, 0".into())
+    );
+    assert_eq!(
+        c.execute("rm +5 Dexterity Vest"),
+        Ok("removed +5 Dexterity Vest, 10, 20".into())
+    );
+    let names: Vec<&str> = c.rose().items.iter().map(|it| it.name.as_str()).collect();
+    assert_eq!(
+        names,
+        ["Conjured Aged Brie", PASS_TAFKAL, "Elixir", "Elixir"]
+    );
+}
+
+#[test]
+fn console_rejects_invalid_changes() {
+    let mut c = console();
+    assert!(c.execute("add Elixir=5,51").is_err());
+    assert!(c.execute("add Sulfuras, Hand of Ragnaros=0,50").is_err());
+    assert!(c.execute("edit 0 quality=-1").is_err());
+    assert!(c.execute("edit 0 colour=red").is_err());
+    assert!(c.execute("rm 3").is_err());
+    assert!(c.execute("tick 0").is_err());
+    assert_eq!(
+        c.execute("add Elixir=5,10,4000000000"),
+        Err("usage: add NAME=SELL_IN,QUALITY[,N], N in 1..=1000".into())
+    );
+    assert!(c.execute("add Elixir=5,10,0").is_err());
+    assert!(c.execute("sell 0").is_err());
+    assert_eq!(c.rose().items.len(), 3);
+    assert_eq!(c.execute("undo"), Err("nothing to undo".into()));
+}
+
+#[test]
+fn console_ticks_shows_and_filters() {
+    let mut c = console();
+    c.execute("tick 2").expect("valid");
+    assert_eq!(
+        c.execute("show"),
+        Ok(format!(
+            "day 2: index, name, sellIn, quality\n  0  +5 Dexterity Vest, 8, 18\n  1  Aged Brie, 0, 2\n  2  {}, 13, 22",
+            PASS_TAFKAL
+        ))
+    );
+    assert_eq!(
+        c.execute("filter kind:appreciating"),
+        Ok("day 2: index, name, sellIn, quality\n  1  Aged Brie, 0, 2".into())
+    );
+    assert_eq!(
+        c.execute("filter *Vest"),
+        Ok("day 2: index, name, sellIn, quality\n  0  +5 Dexterity Vest, 8, 18".into())
+    );
+}
+
+#[test]
+fn console_undo_replays_the_journal() {
+    let mut c = console();
+    c.execute("tick").expect("valid");
+    c.execute("rm 0").expect("valid");
+    c.execute("tick 3").expect("valid");
+    assert_eq!(c.execute("undo"), Ok("day 1, 2 items".into()));
+    assert_eq!(c.execute("undo"), Ok("day 1, 3 items".into()));
+    assert_eq!(c.rose().items[0].sell_in, 9);
+    assert_eq!(c.execute("undo"), Ok("day 0, 3 items".into()));
+    assert_eq!(c.rose().items[1].quality, 0);
+}
+
+#[test]
+fn console_explains_the_last_tick() {
+    let mut c = console();
+    let before = c.execute("explain 1").expect("item 1");
+    assert!(before.starts_with(
+        "Aged Brie (appreciating, not ticked since it changed; next tick): 2, 0 -> 1, 1"
+    ));
+    c.execute("tick 2").expect("valid");
+    assert_eq!(
+        c.execute("explain Aged Brie"),
+        Ok(
+            "Aged Brie (appreciating, last tick): 1, 1 -> 0, 2\n  - appreciating: gains 1 per day"
+                .into()
+        )
+    );
+}
+
+#[test]
+fn console_completes_commands_and_item_names() {
+    let c = console();
+    assert_eq!(c.complete("ti"), ["tick "]);
+    assert_eq!(c.complete("e"), ["edit ", "explain "]);
+    assert_eq!(c.complete("rm A"), ["rm Aged Brie"]);
+    assert_eq!(
+        c.complete("explain Backstage"),
+        [format!("explain {}", PASS_TAFKAL)]
+    );
+    assert!(c.complete("tick A").is_empty());
+}
+
+#[test]
+fn console_runs_lines_and_keeps_history() {
+    let mut c = console();
+    let mut out = Vec::new();
+    c.run("tick\nbogus\nhistory\nquit\ntick\n".as_bytes(), &mut out)
+        .expect("in-memory io");
+    let out = String::from_utf8(out).expect("utf-8");
+    assert!(out.contains("error: unknown command: bogus (try help)\n"));
+    assert!(out.ends_with("   1  tick\n   2  bogus\n   3  history\n"));
+    assert_eq!(c.rose().day(), 1);
+}
+
+#[test]
+fn cli_parses_repl() {
+    let opts = cli::parse(["--repl", "stdio"].map(String::from)).expect("valid flags");
+    assert!(opts.repl);
+}
+
+//
+// Stores
+//
+
+fn two_stores() -> Stores {
+    let mut stores = Stores::new();
+    stores
+        .add_store(
+            "north",
+            rose_with(vec![mk("Aged Brie", 2, 0), mk("Elixir", 5, 7)]),
+        )
+        .expect("new store");
+    stores
+        .add_store("south", rose_with(vec![mk(PASS_TAFKAL, 15, 20)]))
+        .expect("new store");
+    stores
+}
+
+#[test]
+fn stores_tick_on_the_same_day() {
+    let mut stores = two_stores();
+    stores.tick();
+    stores.tick();
+    assert_eq!(stores.day(), 2);
+    for name in ["north", "south"] {
+        assert_eq!(stores.get(name).map(GildedRose::day), Some(2));
+    }
+    let late = rose_with(Vec::new());
+    assert_eq!(
+        stores.add_store("east", late),
+        Err(StoreError::DayMismatch {
+            store: "east".into(),
+            day: 0,
+            expected: 2
+        })
+    );
+    assert_eq!(
+        stores.add_store("north", rose_with(Vec::new())),
+        Err(StoreError::DuplicateStore("north".into()))
+    );
+    // The report's total row is named "all", so no store may be.
+    assert_eq!(
+        stores.add_store(STORE_TOTAL, rose_with(Vec::new())),
+        Err(StoreError::ReservedName("all".into()))
+    );
+    assert_eq!(stores.report().stores.len(), 2);
+}
+
+#[test]
+fn stores_transfer_keeps_values_and_records_it() {
+    let mut stores = two_stores();
+    stores.tick();
+    let t = stores
+        .transfer("north", 1, "south")
+        .expect("valid transfer")
+        .clone();
+    assert_eq!(
+        t,
+        Transfer {
+            day: 1,
+            from: "north".into(),
+            to: "south".into(),
+            name: "Elixir".into(),
+            sell_in: 4,
+            quality: 6
+        }
+    );
+    assert_eq!(t.to_string(), "day 1, north -> south: Elixir, 4, 6");
+    let south = stores.get("south").expect("store");
+    assert_eq!((south.items[1].sell_in, south.items[1].quality), (4, 6));
+    assert_eq!(stores.get("north").map(|r| r.items.len()), Some(1));
+    assert_eq!(stores.transfers().len(), 1);
+}
+
+#[test]
+fn stores_transfer_errors_move_nothing() {
+    let mut stores = two_stores();
+    let strict = Profile {
+        legendaries: vec![Legendary::parse("Aged Brie=80").expect("valid")],
+        ..Profile::default()
+    };
+    stores
+        .add_store("vault", GildedRose::with_profile(Vec::new(), strict))
+        .expect("new store");
+    assert_eq!(
+        stores.transfer("north", 0, "north"),
+        Err(StoreError::SameStore("north".into()))
+    );
+    assert_eq!(
+        stores.transfer("north", 5, "south"),
+        Err(StoreError::NoSuchItem {
+            store: "north".into(),
+            index: 5
+        })
+    );
+    assert_eq!(
+        stores.transfer("west", 0, "south"),
+        Err(StoreError::UnknownStore("west".into()))
+    );
+    assert!(matches!(
+        stores.transfer("north", 0, "vault"),
+        Err(StoreError::Rejected { .. })
+    ));
+    assert_eq!(stores.get("north").map(|r| r.items.len()), Some(2));
+    assert!(stores.transfers().is_empty());
+}
+
+#[test]
+fn stores_report_per_store_and_consolidated() {
+    let mut stores = two_stores();
+    stores
+        .transfer("north", 1, "south")
+        .expect("valid transfer");
+    assert_eq!(
+        stores.store_report("south").map(|r| r.to_string()),
+        Ok("south, 2, 27, 0, 1, 0, backstage 1 normal 1".into())
+    );
+    assert_eq!(
+        stores.report().to_string(),
+        "day 0\n\
+         store, items, quality, expired, in, out, kinds\n\
+         north, 1, 0, 0, 0, 1, appreciating 1\n\
+         south, 2, 27, 0, 1, 0, backstage 1 normal 1\n\
+         all, 3, 27, 0, 0, 0, appreciating 1 backstage 1 normal 1"
+    );
+    assert!(stores.store_report("west").is_err());
+}
+
+//
+// Report rendering
+//
+
+#[test]
+fn day_report_groups_by_kind_and_totals() {
+    let r = rose_with(vec![
+        mk("Elixir", -1, 0),
+        mk("Aged Brie", 2, 0),
+        mk("Sulfuras, Hand of Ragnaros", -1, 80),
+        mk("+5 Dexterity Vest", 10, 20),
+    ]);
+    let report = DayReport::of(&r);
+    let groups: Vec<(&str, usize)> = report
+        .groups
+        .iter()
+        .map(|(l, rows)| (*l, rows.len()))
+        .collect();
+    assert_eq!(
+        groups,
+        [("appreciating", 1), ("legendary", 1), ("normal", 2)]
+    );
+    assert_eq!(
+        report.totals(),
+        Totals {
+            items: 4,
+            quality: 100,
+            expired: 1,
+            worthless: 2
+        }
+    );
+}
+
+#[test]
+fn markdown_report_flags_rows() {
+    let mut r = rose_with(vec![mk("Elixir | Tonic", 0, 1)]);
+    let days = render::simulate(&mut r, 2);
+    assert_eq!(r.day(), 2);
+    assert_eq!(
+        render::markdown("Stand-up", &days),
+        "# Stand-up\n\n\
+         ## Day 0\n\n\
+         ### normal\n\n\
+         | Name | Sell in | Quality | Status |\n\
+         |---|---:|---:|---|\n\
+         | Elixir \\| Tonic | 0 | 1 |  |\n\n\
+         **Totals:** 1 items, quality 1, 0 expired, 0 at quality 0\n\n\
+         ## Day 1\n\n\
+         ### normal\n\n\
+         | Name | Sell in | Quality | Status |\n\
+         |---|---:|---:|---|\n\
+         | Elixir \\| Tonic | -1 | 0 | **expired, quality 0** |\n\n\
+         **Totals:** 1 items, quality 0, 1 expired, 1 at quality 0\n"
+    );
+}
+
+#[test]
+fn html_report_is_escaped_and_standalone() {
+    let r = rose_with(vec![mk("<b>Brie</b> & co", -2, 0), mk("Aged Brie", 3, 4)]);
+    let page = render::render(Format::Html, "Day <1>", &[DayReport::of(&r)]);
+    assert!(page.starts_with("<!DOCTYPE html>\n"));
+    assert!(page.ends_with("</body>\n</html>\n"));
+    assert!(page.contains("<title>Day &lt;1&gt;</title>"));
+    assert!(page.contains(
+        "<tr class=\"expired worthless\"><td>&lt;b&gt;Brie&lt;/b&gt; &amp; co</td><td class=\"num\">-2</td>"
+    ));
+    assert!(page.contains("<tr><td>Aged Brie</td>"));
+    assert!(
+        page.contains(
+            "<p><strong>Totals:</strong> 2 items, quality 4, 1 expired, 1 at quality 0</p>"
+        )
+    );
+}
+
+#[test]
+fn cli_parses_report() {
+    let opts = cli::parse(["--report", "md"].map(String::from)).expect("valid flags");
+    assert_eq!(opts.report, Some(Format::Markdown));
+    assert!(cli::parse(["--report", "pdf"].map(String::from)).is_err_and(|e| e == CliError::Usage));
+}
+
+//
+// Charts
+//
+
+#[test]
+fn trajectories_mark_sell_by_and_backstage_drop() {
+    let mut r = rose_with(vec![
+        mk(PASS_TAFKAL, 2, 10),
+        mk("Elixir", 1, 3),
+        mk("Sulfuras, Hand of Ragnaros", -1, 80),
+    ]);
+    let series = chart::trajectories(&mut r, 4);
+    assert_eq!(r.day(), 4);
+    assert_eq!(series[0].label, format!("#0 {}", PASS_TAFKAL));
+    assert_eq!(
+        series[0].points,
+        [(0, 10), (1, 13), (2, 16), (3, 0), (4, 0)]
+    );
+    assert_eq!((series[0].sell_by, series[0].drop), (Some(2), Some(3)));
+    assert_eq!((series[1].sell_by, series[1].drop), (Some(1), None));
+    assert_eq!((series[2].sell_by, series[2].drop), (None, None));
+}
+
+#[test]
+fn chart_selects_items_or_kinds() {
+    let mut r = rose_with(vec![
+        mk("Elixir", 5, 7),
+        mk("+5 Dexterity Vest", 5, 20),
+        mk("Aged Brie", 5, 0),
+    ]);
+    let series = chart::trajectories(&mut r, 1);
+    let picked = chart::select(series.clone(), &Selection::Items(vec!["*Vest".into()]));
+    assert_eq!(picked.len(), 1);
+    assert_eq!(picked[0].label, "#1 +5 Dexterity Vest");
+    let kinds = chart::select(series, &Selection::Kinds);
+    let labels: Vec<&str> = kinds.iter().map(|s| s.label.as_str()).collect();
+    assert_eq!(labels, ["appreciating (1)", "normal (2)"]);
+    // (7 + 20) / 2 and (6 + 19) / 2, rounded down.
+    assert_eq!(kinds[1].points, [(0, 13), (1, 12)]);
+}
+
+#[test]
+fn svg_chart_draws_series_markers_and_legend() {
+    let mut r = rose_with(vec![mk(PASS_TAFKAL, 1, 10), mk("<Elixir>", 5, 7)]);
+    let series = chart::trajectories(&mut r, 3);
+    let svg = chart::svg("Passes & co", &series);
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.ends_with("</svg>\n"));
+    assert!(svg.contains(">Passes &amp; co</text>"));
+    assert!(svg.contains("&lt;Elixir&gt;"));
+    assert_eq!(svg.matches("class=\"series\"").count(), 2);
+    assert_eq!(svg.matches("class=\"sell-by\"").count(), 1);
+    assert_eq!(svg.matches("class=\"drop\"").count(), 1);
+    assert!(svg.contains("dropped to 0 on day 2"));
+}
+
+#[test]
+fn cli_parses_chart() {
+    let opts = cli::parse(["--chart", "Backstage*", "--chart", "Aged*"].map(String::from))
+        .expect("valid flags");
+    assert_eq!(
+        opts.chart,
+        Some(Selection::Items(vec!["Backstage*".into(), "Aged*".into()]))
+    );
+    let opts = cli::parse(["--chart", "kinds"].map(String::from)).expect("valid flags");
+    assert_eq!(opts.chart, Some(Selection::Kinds));
+}
+
+//
+// Inventory diffs
+//
+
+#[test]
+fn diff_matches_by_name_and_occurrence() {
+    let before = [
+        mk("Elixir", 5, 7),
+        mk("Aged Brie", 2, 0),
+        mk("Elixir", 3, 4),
+        mk("+5 Dexterity Vest", 10, 20),
+    ];
+    let after = [
+        mk("Aged Brie", 2, 0),
+        mk("Elixir", 5, 7),
+        mk("Elixir", 3, 9),
+        mk("Sulfuras, Hand of Ragnaros", 0, 80),
+    ];
+    let d = Diff::between(&before, &after, Matching::Name);
+    assert_eq!(
+        d.removed,
+        [Entry {
+            index: 3,
+            name: "+5 Dexterity Vest".into(),
+            sell_in: 10,
+            quality: 20
+        }]
+    );
+    assert_eq!(d.added.len(), 1);
+    assert_eq!(d.added[0].index, 3);
+    // The second Elixir before is the second Elixir after; moved but unchanged items are left out.
+    assert_eq!(
+        d.modified,
+        [Modified {
+            before: 2,
+            after: 2,
+            name: "Elixir".into(),
+            fields: vec![("quality", 4, 9)]
+        }]
+    );
+}
+
+#[test]
+fn diff_by_position_pairs_indexes_only() {
+    let before = [mk("Vest", 10, 20), mk("Elixir", 5, 7)];
+    let after = [mk("Elixir", 5, 7)];
+    let d = Diff::between(&before, &after, Matching::Name);
+    assert_eq!(
+        (d.removed.len(), d.added.len(), d.modified.len()),
+        (1, 0, 0)
+    );
+    // Index 0 now holds another name: a removal and an addition.
+    let d = Diff::between(&before, &after, Matching::Position);
+    assert_eq!(
+        (d.removed.len(), d.added.len(), d.modified.len()),
+        (2, 1, 0)
+    );
+    let d = Diff::between(&after, &[mk("Elixir", 4, 6)], Matching::Position);
+    assert_eq!(d.modified[0].fields, [("sell_in", 5, 4), ("quality", 7, 6)]);
+}
+
+#[test]
+fn diff_renders_text_and_json() {
+    let before = [mk("Elixir", 5, 7), mk("Aged Brie", 2, 0)];
+    let after = [mk("Aged Brie", 1, 1), mk("Vest", 10, 20)];
+    let d = Diff::between(&before, &after, Matching::Name);
+    assert_eq!(
+        d.to_string(),
+        "1 added, 1 removed, 1 modified\n\
+         - #0 Elixir, 5, 7\n\
+         + #1 Vest, 10, 20\n\
+         ~ #0 (was #1) Aged Brie: sell_in 2 -> 1, quality 0 -> 1"
+    );
+    assert_eq!(
+        d.to_json().to_string(),
+        r#"{"added":[{"index":1,"name":"Vest","sell_in":10,"quality":20}],"removed":[{"index":0,"name":"Elixir","sell_in":5,"quality":7}],"modified":[{"before":1,"after":0,"name":"Aged Brie","changes":{"sell_in":{"before":2,"after":1},"quality":{"before":0,"after":1}}}]}"#
+    );
+    assert_eq!(Diff::default().to_string(), "no changes");
+}
+
+#[test]
+fn service_diffs_days() {
+    let mut s = service();
+    call(&mut s, "POST", "/tick", "");
+    call(&mut s, "DELETE", "/items/0", "");
+    let (status, d) = call(&mut s, "GET", "/diff/0", "");
+    assert_eq!(status, 200);
+    assert_eq!(d.get("to").and_then(Json::as_i64), Some(1));
+    let len = |key| d.get(key).and_then(Json::as_array).map(<[Json]>::len);
+    assert_eq!(
+        (len("added"), len("removed"), len("modified")),
+        (Some(0), Some(1), Some(2))
+    );
+    let (_, same) = call(&mut s, "GET", "/diff/1?to=1&match=position", "");
+    assert_eq!(
+        same.to_string(),
+        r#"{"from":1,"to":1,"added":[],"removed":[],"modified":[]}"#
+    );
+    assert_eq!(call(&mut s, "GET", "/diff/2", "").0, 404);
+    assert_eq!(call(&mut s, "GET", "/diff/0?to=9", "").0, 404);
+    assert_eq!(call(&mut s, "GET", "/diff/0?match=id", "").0, 400);
+    assert_eq!(call(&mut s, "POST", "/diff/0", "").0, 405);
+}
+
+#[test]
+fn cli_parses_diff() {
+    let opts = cli::parse(["--diff", "json"].map(String::from)).expect("valid flags");
+    assert_eq!(opts.diff, Some(diff::Format::Json));
+    assert!(cli::parse(["--diff", "yaml"].map(String::from)).is_err_and(|e| e == CliError::Usage));
+}
+
+//
+// Name aliases
+//
+
+const ALIASES: &str = "\
+# supplier spellings
+Brie, aged = Aged Brie
+Sulfuras = Sulfuras, Hand of Ragnaros
+
+[fr]
+Brie affiné = Aged Brie
+Billets de concert * = kind:backstage
+[de]
+Mjölnir = kind:legendary:90
+Aged Brie = kind:normal
+";
+
+fn profile_with_aliases() -> Profile {
+    Profile {
+        aliases: alias::parse(ALIASES).expect("valid table"),
+        ..Profile::default()
+    }
+}
+
+#[test]
+fn alias_table_parses_languages_and_targets() {
+    let aliases = alias::parse(ALIASES).expect("valid table");
+    assert_eq!(aliases.len(), 6);
+    assert_eq!(
+        aliases[0],
+        Alias {
+            pattern: "Brie, aged".into(),
+            language: None,
+            target: AliasTarget::Name("Aged Brie".into()),
+        }
+    );
+    assert_eq!(aliases[2].language.as_deref(), Some("fr"));
+    assert_eq!(aliases[3].target, AliasTarget::Kind(AliasKind::Backstage));
+    assert_eq!(
+        aliases[4].target,
+        AliasTarget::Kind(AliasKind::Legendary(90))
+    );
+    assert_eq!(aliases[4].language.as_deref(), Some("de"));
+    assert_eq!(aliases[4].target.to_string(), "kind:legendary:90");
+}
+
+#[test]
+fn alias_table_errors_name_the_line() {
+    let err = |src: &str| alias::parse(src).expect_err("invalid table");
+    let syntax = |line, message: &str| AliasError::Syntax {
+        line,
+        message: message.into(),
+    };
+    assert_eq!(
+        err("a = b\nno target"),
+        syntax(2, "expected ALIAS = TARGET")
+    );
+    assert_eq!(err(" = Aged Brie"), syntax(1, "expected ALIAS = TARGET"));
+    assert_eq!(err("[fr\n"), syntax(1, "expected [LANGUAGE]"));
+    assert_eq!(err("a = b\n[fr]\na = c"), syntax(3, "alias defined twice"));
+    assert!(matches!(
+        err("a = kind:wine"),
+        AliasError::Syntax { line: 1, .. }
+    ));
+    assert!(matches!(
+        alias::load("/nonexistent/aliases.txt"),
+        Err(AliasError::Io(_))
+    ));
+}
+
+#[test]
+fn aliases_are_classified_as_their_target() {
+    let mut r = rose_with_profile(
+        vec![
+            mk("Brie, aged", 2, 0),
+            mk("Sulfuras", 0, 80),
+            mk("Billets de concert Daft Punk", 5, 10),
+            mk("Mjölnir", 3, 90),
+            mk("Conjured Brie affiné", 2, 10),
+            mk("Brie", 2, 10),
+        ],
+        profile_with_aliases(),
+    );
+    tick(&mut r);
+    let state: Vec<(i32, i32)> = r.items.iter().map(|it| (it.sell_in, it.quality)).collect();
+    assert_eq!(state, [(1, 1), (0, 80), (4, 13), (3, 90), (1, 11), (1, 9)]);
+    assert_eq!(r.kind_label("Brie, aged"), "appreciating");
+    // A target name is not looked up as an alias again.
+    assert_eq!(r.kind_label("Brie affiné"), "appreciating");
+    assert_eq!(r.kind_label("Aged Brie"), "normal");
+}
+
+#[test]
+fn alias_usage_reports_items_in_table_order() {
+    let r = rose_with_profile(
+        vec![
+            mk("Sulfuras", 0, 80),
+            mk("Brie, aged", 2, 0),
+            mk("Elixir", 5, 7),
+            mk("Conjured Brie, aged", 2, 0),
+        ],
+        profile_with_aliases(),
+    );
+    let uses = alias::usage(r.profile(), &r.items);
+    let lines: Vec<String> = uses.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        lines,
+        [
+            "Brie, aged, -, Aged Brie, #1 #3",
+            "Sulfuras, -, Sulfuras, Hand of Ragnaros, #0"
+        ]
+    );
+    let e = Explanation::of(&r, 1).expect("item exists");
+    assert_eq!(e.reasons[0], "alias of Aged Brie");
+}
+
+#[test]
+fn cli_parses_aliases() {
+    let opts = cli::parse(["--aliases", "names.txt"].map(String::from)).expect("valid flags");
+    assert_eq!(opts.aliases.as_deref(), Some("names.txt"));
+}
+
+//
+// Near-miss names
+//
+
+fn suggestion(profile: &Profile, name: &str) -> Option<String> {
+    lint::check(profile, name).map(|n| n.suggestion)
+}
+
+#[test]
+fn lint_flags_case_whitespace_and_typos() {
+    let p = Profile::default();
+    assert_eq!(
+        lint::check(&p, "sulfuras, hand of ragnaros"),
+        Some(NearMiss {
+            name: "sulfuras, hand of ragnaros".into(),
+            suggestion: "Sulfuras, Hand of Ragnaros".into(),
+            reasons: vec![r#""Sulfuras, Hand of Ragnaros": only case differs"#.into()],
+        })
+    );
+    assert_eq!(suggestion(&p, "Aged  Brie "), Some("Aged Brie".into()));
+    assert_eq!(suggestion(&p, " aged brie"), Some("Aged Brie".into()));
+    assert_eq!(suggestion(&p, "Aged Bree"), Some("Aged Brie".into()));
+    assert_eq!(
+        suggestion(&p, "Sulfurus, Hand of Ragnaros"),
+        Some("Sulfuras, Hand of Ragnaros".into())
+    );
+    assert_eq!(
+        suggestion(&p, "Backstage pases to a TAFKAL80ETC concert"),
+        Some(PASS_TAFKAL.into())
+    );
+    assert_eq!(
+        suggestion(&p, "backstage passes to a TAFKAL80ETC concert"),
+        Some(PASS_TAFKAL.into())
+    );
+    assert_eq!(
+        lint::check(&p, "Aged Bree").map(|n| n.to_string()),
+        Some(r#""Aged Bree" looks like "Aged Brie" ("Aged Brie": 1 edit away)"#.into())
+    );
+}
+
+#[test]
+fn lint_checks_modifiers_before_the_base_name() {
+    let p = Profile::default();
+    assert_eq!(
+        suggestion(&p, "conjured Elixir"),
+        Some("Conjured Elixir".into())
+    );
+    assert_eq!(
+        suggestion(&p, "Conjurd Elixir"),
+        Some("Conjured Elixir".into())
+    );
+    let near = lint::check(&p, "Conjured aged brie").expect("near miss");
+    assert_eq!(near.suggestion, "Conjured Aged Brie");
+    let near = lint::check(&modifiers_profile(), "conjured Blessed aged brie").expect("near miss");
+    assert_eq!(near.suggestion, "Conjured Blessed Aged Brie");
+    assert_eq!(near.reasons.len(), 2);
+    // Under the classic profile "Conjured " is not a modifier at all.
+    assert_eq!(
+        suggestion(&Profile::classic_kata(), "conjured Elixir"),
+        None
+    );
+}
+
+#[test]
+fn lint_leaves_exact_and_unrelated_names_alone() {
+    let p = Profile::default();
+    for name in [
+        "Aged Brie",
+        "Sulfuras, Hand of Ragnaros",
+        PASS_TAFKAL,
+        "Backstage passes",
+        "Conjured Aged Brie",
+        "Elixir of the Mongoose",
+        "+5 Dexterity Vest",
+        "Brie",
+        "Cured Ham",
+        "Agde Brie",
+        "",
+    ] {
+        assert_eq!(lint::check(&p, name), None, "{}", name);
+    }
+}
+
+#[test]
+fn lint_uses_registries_and_aliases() {
+    let p = Profile {
+        legendaries: vec![Legendary::parse("Thunderfury*=90").expect("valid")],
+        aliases: alias::parse("Brie, aged = Aged Brie").expect("valid"),
+        ..Profile::default()
+    };
+    assert_eq!(
+        suggestion(&p, "thunderfury, Blessed Blade"),
+        Some("Thunderfury, Blessed Blade".into())
+    );
+    assert_eq!(suggestion(&p, "Brie,  aged"), Some("Brie, aged".into()));
+    // The built-in Sulfuras was replaced, so it is no longer a special name.
+    assert_eq!(suggestion(&p, "sulfuras, hand of ragnaros"), None);
+}
+
+#[test]
+fn gilded_rose_lint_lists_items_and_skips_custom_kinds() {
+    let mut r = rose_with(vec![
+        mk("Elixir", 5, 7),
+        mk("aged brie", 2, 0),
+        mk("fine wine 1990", 1, 10),
+    ]);
+    assert_eq!(
+        r.lint()
+            .into_iter()
+            .map(|(i, n)| (i, n.suggestion))
+            .collect::<Vec<_>>(),
+        [(1, "Aged Brie".to_string())]
+    );
+    r.add_behavior(Box::new(Frozen));
+    assert!(r.lint().is_empty());
+    // Warnings only: validation and updates still go by the exact name.
+    assert!(rose_with(vec![mk("aged brie", 2, 0)]).validate().is_empty());
+}
+
+#[test]
+fn rpc_validate_lists_near_misses() {
+    let mut s = session();
+    s.handle_line(
+        r#"{"jsonrpc":"2.0","id":1,"method":"load","params":{"items":[{"name":"Aged brie","sell_in":2,"quality":0}]}}"#,
+    );
+    let answer = s
+        .handle_line(r#"{"jsonrpc":"2.0","id":2,"method":"validate"}"#)
+        .expect("answered");
+    assert_eq!(
+        answer,
+        r#"{"jsonrpc":"2.0","id":2,"result":[{"index":0,"reason":"near miss: \"Aged Brie\": only case differs","suggestion":"Aged Brie"}]}"#
+    );
+}
+
+#[test]
+fn console_warns_about_near_miss_names() {
+    let mut c = console();
+    let added = c.execute("add aged brie=5,10").expect("added");
+    assert!(added.ends_with(
+        "\nwarning: \"aged brie\" looks like \"Aged Brie\" (\"Aged Brie\": only case differs)"
+    ));
+    let edited = c.execute("edit 0 name=Sulfuras").expect("edited");
+    assert!(!edited.contains("warning"));
+    assert!(
+        c.execute("edit 0 quality=3")
+            .expect("edited")
+            .lines()
+            .count()
+            == 1
+    );
+}